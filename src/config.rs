@@ -0,0 +1,345 @@
+use iced_wgpu::wgpu;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: integration [OPTIONS]
+       integration golden [--update] [NAME...]
+
+Options:
+    --config <file>          Configuration file to use instead of the default one
+    --title <title>          Window title
+    --width <pixels>         Window width
+    --height <pixels>        Window height
+    --backend <name>         Graphics backend: auto, primary, vulkan, metal, dx12, gl or all
+    --adapter <name>         Use the adapter with this in its name
+    --list-adapters          List the available adapters and exit
+    --present-mode <mode>    fifo, fifo_relaxed, mailbox, immediate, auto_vsync or auto_no_vsync
+    --scale-factor <factor>  UI scale, instead of working it out from the display
+    --fps-cap <fps>          Limit the frame rate
+    --lua <file>             Lua script to run instead of the built-in one
+    --record <file>          Record input to a file
+    --replay <file>          Play back input recorded with --record
+    --help                   Show this help";
+
+/// The configuration file as written, where everything is optional.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    title: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    backend: Option<String>,
+    adapter: Option<String>,
+    present_mode: Option<String>,
+    scale_factor: Option<f64>,
+    fps_cap: Option<f64>,
+    lua: Option<PathBuf>,
+}
+
+/// Startup options, from the defaults, the configuration file and the command line, in that
+/// order of precedence.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    /// Backends to use, or `None` to try them all in order of preference.
+    pub backends: Option<wgpu::Backends>,
+    /// Part of the name of the adapter to use.
+    pub adapter: Option<String>,
+    pub present_mode: wgpu::PresentMode,
+    /// Overrides the scale factor worked out from the display.
+    pub scale_factor: Option<f64>,
+    pub fps_cap: Option<f64>,
+    /// Lua script to run instead of the built-in one.
+    pub lua: Option<PathBuf>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub list_adapters: bool,
+    /// Only show the usage.
+    pub help: bool,
+    /// The configuration file, whether or not it exists. Files such as the bindings are kept
+    /// next to it.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            title: String::from("Raw Window Handle Example"),
+            width: 800,
+            height: 600,
+            backends: None,
+            adapter: None,
+            present_mode: wgpu::PresentMode::Fifo,
+            scale_factor: None,
+            fps_cap: None,
+            lua: None,
+            record: None,
+            replay: None,
+            list_adapters: false,
+            help: false,
+            path: None,
+        }
+    }
+}
+
+/// Where the configuration file is looked for when not given on the command line.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("naev").join("config.toml"))
+}
+
+fn parse_backends(s: &str) -> Result<Option<wgpu::Backends>, String> {
+    Ok(Some(match s {
+        "auto" => return Ok(None),
+        "primary" => wgpu::Backends::PRIMARY,
+        "vulkan" => wgpu::Backends::VULKAN,
+        "metal" => wgpu::Backends::METAL,
+        "dx12" => wgpu::Backends::DX12,
+        "gl" => wgpu::Backends::GL,
+        "all" => wgpu::Backends::all(),
+        _ => {
+            return Err(format!(
+                "Unknown backend '{}', expected auto, primary, vulkan, metal, dx12, gl or all",
+                s
+            ))
+        }
+    }))
+}
+
+fn parse_present_mode(s: &str) -> Result<wgpu::PresentMode, String> {
+    Ok(match s {
+        "fifo" => wgpu::PresentMode::Fifo,
+        "fifo_relaxed" => wgpu::PresentMode::FifoRelaxed,
+        "mailbox" => wgpu::PresentMode::Mailbox,
+        "immediate" => wgpu::PresentMode::Immediate,
+        "auto_vsync" => wgpu::PresentMode::AutoVsync,
+        "auto_no_vsync" => wgpu::PresentMode::AutoNoVsync,
+        _ => {
+            return Err(format!(
+                "Unknown present mode '{}', expected fifo, fifo_relaxed, mailbox, immediate, \
+                 auto_vsync or auto_no_vsync",
+                s
+            ))
+        }
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, s: &str) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("Invalid value '{}' for {}, expected a number", s, option))
+}
+
+/// The command line, read in one pass before anything is applied, as the configuration file it
+/// overrides can be given anywhere on it. Options that can also go in the file are kept as one.
+#[derive(Debug, Default)]
+struct Args {
+    config: Option<PathBuf>,
+    overrides: ConfigFile,
+    record: Option<String>,
+    replay: Option<String>,
+    list_adapters: bool,
+    help: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value\n\n{}", arg, USAGE))
+        };
+        let o = &mut parsed.overrides;
+        match arg.as_str() {
+            "--help" => parsed.help = true,
+            "--list-adapters" => parsed.list_adapters = true,
+            "--config" => parsed.config = Some(PathBuf::from(value()?)),
+            "--title" => o.title = Some(value()?),
+            "--width" => o.width = Some(parse_number(&arg, &value()?)?),
+            "--height" => o.height = Some(parse_number(&arg, &value()?)?),
+            "--backend" => o.backend = Some(value()?),
+            "--adapter" => o.adapter = Some(value()?),
+            "--present-mode" => o.present_mode = Some(value()?),
+            "--scale-factor" => o.scale_factor = Some(parse_number(&arg, &value()?)?),
+            "--fps-cap" => o.fps_cap = Some(parse_number(&arg, &value()?)?),
+            "--lua" => o.lua = Some(PathBuf::from(value()?)),
+            "--record" => parsed.record = Some(value()?),
+            "--replay" => parsed.replay = Some(value()?),
+            _ => return Err(format!("Unknown option '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+impl Config {
+    /// Reads the configuration file and applies the command line over it. The configuration
+    /// file is optional unless given with `--config`.
+    pub fn load(args: impl Iterator<Item = String>) -> Result<Config, String> {
+        let args = parse_args(args)?;
+        let mut config = Config::default();
+        if args.help {
+            config.help = true;
+            return Ok(config);
+        }
+
+        let (path, required) = match &args.config {
+            Some(path) => (Some(path.clone()), true),
+            None => (default_path(), false),
+        };
+        config.path = path.clone();
+        if let Some(path) = path.filter(|path| required || path.exists()) {
+            let data =
+                std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let file: ConfigFile =
+                toml::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+            config
+                .apply_file(file)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        config.apply_args(args)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_file(&mut self, file: ConfigFile) -> Result<(), String> {
+        if let Some(title) = file.title {
+            self.title = title;
+        }
+        if let Some(width) = file.width {
+            self.width = width;
+        }
+        if let Some(height) = file.height {
+            self.height = height;
+        }
+        if let Some(backend) = file.backend {
+            self.backends = parse_backends(&backend)?;
+        }
+        if file.adapter.is_some() {
+            self.adapter = file.adapter;
+        }
+        if let Some(present_mode) = file.present_mode {
+            self.present_mode = parse_present_mode(&present_mode)?;
+        }
+        if file.scale_factor.is_some() {
+            self.scale_factor = file.scale_factor;
+        }
+        if file.fps_cap.is_some() {
+            self.fps_cap = file.fps_cap;
+        }
+        if file.lua.is_some() {
+            self.lua = file.lua;
+        }
+        Ok(())
+    }
+
+    fn apply_args(&mut self, args: Args) -> Result<(), String> {
+        self.apply_file(args.overrides)?;
+        if args.record.is_some() {
+            self.record = args.record;
+        }
+        if args.replay.is_some() {
+            self.replay = args.replay;
+        }
+        self.list_adapters = args.list_adapters;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.width < 320 || self.height < 240 {
+            return Err(format!(
+                "Window size {}x{} is too small, it must be at least 320x240",
+                self.width, self.height
+            ));
+        }
+        if let Some(s) = self.scale_factor {
+            if !(0.25..=8.0).contains(&s) {
+                return Err(format!(
+                    "Scale factor {} is out of range, it must be between 0.25 and 8",
+                    s
+                ));
+            }
+        }
+        if let Some(fps) = self.fps_cap {
+            if fps.is_nan() || fps <= 0.0 {
+                return Err(format!("FPS cap {} must be positive", fps));
+            }
+        }
+        if let Some(lua) = &self.lua {
+            if !lua.is_file() {
+                return Err(format!("Lua script '{}' not found", lua.display()));
+            }
+        }
+        if self.record.is_some() && self.record == self.replay {
+            return Err(String::from("Can't record to the file being replayed"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(file: &str, args: &[&str]) -> Result<Config, String> {
+        let mut config = Config::default();
+        config.apply_file(toml::from_str(file).map_err(|e| e.to_string())?)?;
+        config.apply_args(parse_args(args.iter().map(|a| a.to_string()))?)?;
+        Ok(config)
+    }
+
+    #[test]
+    fn precedence() {
+        let file = "title = \"File\"\nwidth = 1024\nheight = 768\nbackend = \"vulkan\"";
+        let config = config(
+            file,
+            &["--width", "1280", "--backend", "gl", "--fps-cap", "30"],
+        )
+        .unwrap();
+        // The command line wins over the file, which wins over the defaults
+        assert_eq!(config.title, "File");
+        assert_eq!(config.width, 1280);
+        assert_eq!(config.height, 768);
+        assert_eq!(config.backends, Some(wgpu::Backends::GL));
+        assert_eq!(config.fps_cap, Some(30.0));
+        assert_eq!(config.present_mode, wgpu::PresentMode::Fifo);
+        assert_eq!(config.scale_factor, None);
+    }
+
+    #[test]
+    fn args() {
+        let args = parse_args(
+            [
+                "--title",
+                "--config",
+                "--config",
+                "a.toml",
+                "--list-adapters",
+            ]
+            .iter()
+            .map(|a| a.to_string()),
+        )
+        .unwrap();
+        // Values are never taken for options
+        assert_eq!(args.overrides.title.as_deref(), Some("--config"));
+        assert_eq!(args.config, Some(PathBuf::from("a.toml")));
+        assert!(args.list_adapters);
+        assert!(!args.help);
+
+        assert!(Config::load(["--help", "--bogus"].iter().map(|a| a.to_string())).is_err());
+        assert!(
+            Config::load(["--width", "10", "--help"].iter().map(|a| a.to_string()))
+                .unwrap()
+                .help
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let err = config("", &["--bogus"]).unwrap_err();
+        assert!(err.starts_with("Unknown option '--bogus'"), "{}", err);
+        let err = config("", &["--width"]).unwrap_err();
+        assert!(err.starts_with("--width needs a value"), "{}", err);
+        assert!(config("", &["--width", "wide"]).is_err());
+        assert!(config("", &["--backend", "glide"]).is_err());
+        assert!(config("fullscreen = true", &[]).is_err());
+    }
+}
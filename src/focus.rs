@@ -0,0 +1,259 @@
+use crate::toolkit::Message;
+use iced_core::layout::{self, Layout};
+use iced_core::widget::operation::Operation;
+use iced_core::widget::{tree, Tree, Widget};
+use iced_core::{
+    event, mouse, overlay, renderer, Clipboard, Element, Length, Rectangle, Shell, Size, Theme,
+    Vector,
+};
+use iced_wgpu::Renderer;
+
+type TextInputState =
+    iced_widget::text_input::State<<Renderer as iced_core::text::Renderer>::Paragraph>;
+type TextEditorState = iced_widget::text_editor::State<iced_core::text::highlighter::PlainText>;
+
+/// Somewhere focus can go: a button, or a focusable widget such as a text input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    pub bounds: Rectangle,
+    /// Position among the focusable widgets in the order operations visit them, for those that
+    /// are focusable.
+    pub focusable: Option<usize>,
+    pub focused: bool,
+    /// Whether text is typed into it, which is what the input method is for.
+    pub text: bool,
+}
+
+/// Wraps a window to tell operations where its stops are. Operations get neither the bounds of
+/// text inputs nor any way to tell a button from a container, so we walk the widget tree along
+/// with the layout instead, and hand the stops to `Operation::custom` before the window itself.
+pub struct Stops<'a> {
+    content: Element<'a, Message, Theme, Renderer>,
+}
+
+impl<'a> Stops<'a> {
+    pub fn new(content: impl Into<Element<'a, Message, Theme, Renderer>>) -> Stops<'a> {
+        Stops {
+            content: content.into(),
+        }
+    }
+}
+
+fn collect(tree: &Tree, layout: Layout<'_>, button: tree::Tag, stops: &mut Vec<Stop>) {
+    let focused = if tree.tag == tree::Tag::of::<TextInputState>() {
+        Some(tree.state.downcast_ref::<TextInputState>().is_focused())
+    } else if tree.tag == tree::Tag::of::<TextEditorState>() {
+        Some(tree.state.downcast_ref::<TextEditorState>().is_focused())
+    } else {
+        None
+    };
+    if let Some(focused) = focused {
+        let focusable = stops.iter().filter(|s| s.focusable.is_some()).count();
+        stops.push(Stop {
+            bounds: layout.bounds(),
+            focusable: Some(focusable),
+            focused,
+            text: true,
+        });
+    } else if tree.tag == button {
+        stops.push(Stop {
+            bounds: layout.bounds(),
+            focusable: None,
+            focused: false,
+            text: false,
+        });
+    }
+    for (child, layout) in tree.children.iter().zip(layout.children()) {
+        collect(child, layout, button, stops);
+    }
+}
+
+impl Widget<Message, Theme, Renderer> for Stops<'_> {
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn size_hint(&self) -> Size<Length> {
+        self.content.as_widget().size_hint()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        )
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        // Button state is private, so we get its tag from a button
+        let button: iced_widget::Button<'_, Message, Theme, Renderer> = iced_widget::button("");
+        let mut stops = Vec::new();
+        collect(&tree.children[0], layout, button.tag(), &mut stops);
+        operation.custom(&mut stops, None);
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: iced_core::Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+}
+
+impl<'a> From<Stops<'a>> for Element<'a, Message, Theme, Renderer> {
+    fn from(stops: Stops<'a>) -> Self {
+        Element::new(stops)
+    }
+}
+
+/// Gets the stops of the window in the container with the id `top`.
+pub struct FindStops {
+    pub top: iced_core::widget::Id,
+    pub in_top: bool,
+    pub stops: std::sync::Arc<std::sync::Mutex<Vec<Stop>>>,
+}
+
+impl Operation for FindStops {
+    fn container(
+        &mut self,
+        id: Option<&iced_core::widget::Id>,
+        _bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation),
+    ) {
+        let in_top = self.in_top;
+        self.in_top |= id == Some(&self.top);
+        operate_on_children(self);
+        self.in_top = in_top;
+    }
+
+    fn custom(&mut self, state: &mut dyn std::any::Any, _id: Option<&iced_core::widget::Id>) {
+        if let (true, Some(stops)) = (self.in_top, state.downcast_ref::<Vec<Stop>>()) {
+            *self.stops.lock().unwrap() = stops.clone();
+        }
+    }
+}
+
+/// Focuses the focusable widget at `target` in the window in the container with the id `top`,
+/// unfocusing the others.
+pub struct Focus {
+    pub top: iced_core::widget::Id,
+    pub in_top: bool,
+    pub target: Option<usize>,
+    pub count: usize,
+}
+
+impl Operation for Focus {
+    fn container(
+        &mut self,
+        id: Option<&iced_core::widget::Id>,
+        _bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation),
+    ) {
+        let in_top = self.in_top;
+        self.in_top |= id == Some(&self.top);
+        operate_on_children(self);
+        self.in_top = in_top;
+    }
+
+    fn focusable(
+        &mut self,
+        state: &mut dyn iced_core::widget::operation::Focusable,
+        _id: Option<&iced_core::widget::Id>,
+    ) {
+        if !self.in_top {
+            return;
+        }
+        match self.target == Some(self.count) {
+            true => state.focus(),
+            false => state.unfocus(),
+        }
+        self.count += 1;
+    }
+}
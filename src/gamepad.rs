@@ -0,0 +1,81 @@
+use crate::toolkit::Navigation;
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use std::collections::HashMap;
+
+/// How far the stick has to be pushed before it counts as a direction.
+const STICK_THRESHOLD: i16 = 16384;
+
+pub struct Gamepads {
+    subsystem: sdl2::GameControllerSubsystem,
+    controllers: HashMap<u32, GameController>,
+    stick: (i8, i8),
+}
+
+impl Gamepads {
+    pub fn new(subsystem: sdl2::GameControllerSubsystem) -> Gamepads {
+        Gamepads {
+            subsystem,
+            controllers: HashMap::new(),
+            stick: (0, 0),
+        }
+    }
+
+    /// Keeps track of controllers being plugged in and out, and turns their input into toolkit
+    /// navigation. Controllers already connected at startup are reported as added by SDL.
+    pub fn handle_event(&mut self, event: &Event) -> Option<Navigation> {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                match self.subsystem.open(*which) {
+                    Ok(c) => {
+                        println!("Opened controller '{}'", c.name());
+                        self.controllers.insert(c.instance_id(), c);
+                    }
+                    Err(e) => println!("Failed to open controller: {}", e),
+                }
+                None
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers.remove(which);
+                None
+            }
+            Event::ControllerButtonDown { button, .. } => match button {
+                Button::DPadUp => Some(Navigation::Up),
+                Button::DPadDown => Some(Navigation::Down),
+                Button::DPadLeft => Some(Navigation::Left),
+                Button::DPadRight => Some(Navigation::Right),
+                Button::A => Some(Navigation::Activate),
+                Button::B => Some(Navigation::Cancel),
+                _ => None,
+            },
+            Event::ControllerAxisMotion { axis, value, .. } => {
+                let dir = if *value > STICK_THRESHOLD {
+                    1
+                } else if *value < -STICK_THRESHOLD {
+                    -1
+                } else {
+                    0
+                };
+                // Only trigger when the stick is pushed out, not while it is held
+                let (old, nav) = match axis {
+                    Axis::LeftX => (
+                        std::mem::replace(&mut self.stick.0, dir),
+                        [Navigation::Left, Navigation::Right],
+                    ),
+                    Axis::LeftY => (
+                        std::mem::replace(&mut self.stick.1, dir),
+                        [Navigation::Up, Navigation::Down],
+                    ),
+                    _ => return None,
+                };
+                match dir {
+                    d if d == old => None,
+                    -1 => Some(nav[0]),
+                    1 => Some(nav[1]),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
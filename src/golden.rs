@@ -0,0 +1,184 @@
+//! Renders the built-in windows offscreen and compares them against the images in `golden/`,
+//! which `cargo test` does through `integration golden`. Run with `--update` to write the images
+//! instead, after checking the changes are wanted.
+use crate::menu_main::MenuMain;
+use crate::nlua::NLua;
+use crate::scene::Scene;
+use crate::toolkit_lua::{self, ToolkitWindowLua};
+use integration::host::{Game, Gpu};
+use integration::offscreen::{self, Offscreen};
+use integration::toolkit::{self, Message};
+
+use iced_wgpu::wgpu;
+use std::path::{Path, PathBuf};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+const SIZES: [(u32, u32); 2] = [(800, 600), (1280, 720)];
+/// How much a channel can be off before the pixel counts as different.
+const TOLERANCE: u8 = 8;
+/// Fraction of pixels that can differ before the image fails.
+const MAX_DIFFERENT: f64 = 0.001;
+
+const USAGE: &str = "Usage: integration golden [--update] [NAME...]
+
+Options:
+    --update  Write the golden images instead of comparing against them
+    --help    Show this help";
+
+struct Background<'a> {
+    scene: Scene<'a>,
+}
+
+impl Game for Background<'_> {
+    fn render(&self, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) -> bool {
+        let mut render_pass = self.scene.clear(view, encoder, iced_core::Color::BLACK);
+        self.scene.draw(&mut render_pass);
+        true
+    }
+}
+
+/// Windows to render, by name.
+fn cases(lua: &mlua::Lua) -> Result<Vec<(&'static str, Option<Message>)>, String> {
+    let globals = lua.globals();
+    let lua_window = ToolkitWindowLua::new(
+        globals.get("update").map_err(|e| e.to_string())?,
+        globals.get("view").map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(vec![
+        ("scene", None),
+        ("menu_main", Some(Message::open(MenuMain::new))),
+        (
+            "dlg_ok",
+            Some(Message::OpenDialogueOK(
+                String::from("This is a dialogue."),
+                &toolkit::dialogue_noop_ok,
+            )),
+        ),
+        (
+            "dlg_input",
+            Some(Message::OpenDialogueInput(
+                String::from("Enter some text:"),
+                &toolkit::dialogue_noop_input,
+            )),
+        ),
+        ("lua", Some(Message::open(move || lua_window.clone()))),
+    ])
+}
+
+/// Compares two images, returning why they don't match if they don't.
+fn compare(expected: &[u8], actual: &[u8]) -> Option<String> {
+    let different = expected
+        .chunks(4)
+        .zip(actual.chunks(4))
+        .filter(|(e, a)| {
+            e.iter()
+                .zip(a.iter())
+                .any(|(e, a)| e.abs_diff(*a) > TOLERANCE)
+        })
+        .count();
+    let fraction = different as f64 / (expected.len() / 4) as f64;
+    match fraction > MAX_DIFFERENT {
+        true => Some(format!(
+            "{} pixels differ ({:.3}%)",
+            different,
+            fraction * 100.0
+        )),
+        false => None,
+    }
+}
+
+/// Checks a rendered image against its golden image, or replaces it when updating.
+fn check(name: &str, width: u32, height: u32, pixels: &[u8], update: bool) -> Result<(), String> {
+    let path = PathBuf::from(GOLDEN_DIR).join(format!("{}.png", name));
+    if update {
+        return offscreen::save_png(&path, width, height, pixels);
+    }
+    if !path.exists() {
+        return Err(format!("{} is missing, run with --update", path.display()));
+    }
+    let (w, h, expected) = offscreen::load_png(&path)?;
+    let failure = match (w, h) == (width, height) {
+        true => compare(&expected, pixels),
+        false => Some(format!(
+            "size is {}x{}, expected {}x{}",
+            width, height, w, h
+        )),
+    };
+    match failure {
+        None => Ok(()),
+        Some(failure) => {
+            let actual = Path::new(GOLDEN_DIR).join(format!("{}.actual.png", name));
+            offscreen::save_png(&actual, width, height, pixels)?;
+            Err(format!("{}, see {}", failure, actual.display()))
+        }
+    }
+}
+
+pub fn main(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut update = false;
+    let mut filter = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--update" => update = true,
+            "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with("--") => {
+                println!("Unknown argument '{}'\n\n{}", arg, USAGE);
+                std::process::exit(1);
+            }
+            _ => filter.push(arg),
+        }
+    }
+    if update {
+        std::fs::create_dir_all(GOLDEN_DIR).map_err(|e| format!("{}: {}", GOLDEN_DIR, e))?;
+    }
+
+    // The software adapter renders the same everywhere, and CI machines don't have a GPU
+    let gpu = Gpu::headless(true)?;
+    println!("Using '{}'", gpu.adapter.get_info().name);
+
+    let nlua = NLua::new();
+    let lua = nlua.lua;
+    toolkit_lua::open_iced(&lua).map_err(|e| e.to_string())?;
+    lua.load(include_str!("main.lua"))
+        .exec()
+        .map_err(|e| e.to_string())?;
+
+    let background = Background {
+        scene: Scene::new(&gpu.device, &gpu.queue, gpu.format),
+    };
+
+    let mut failed = 0;
+    for (width, height) in SIZES {
+        for (name, message) in cases(&lua)? {
+            let name = format!("{}_{}x{}", name, width, height);
+            if !filter.is_empty() && !filter.iter().any(|f| name.contains(f.as_str())) {
+                continue;
+            }
+            let mut offscreen = Offscreen::new(&gpu, width, height, 1.0);
+            if let Some(message) = message {
+                offscreen.toolkit.queue_message(message);
+            }
+            // The first update opens the window and the second lays it out
+            offscreen.update();
+            offscreen.update();
+            let pixels = offscreen.render(Some(&background));
+            match check(&name, width, height, &pixels, update) {
+                Ok(()) => println!("{}: ok", name),
+                Err(e) => {
+                    println!("{}: FAILED: {}", name, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        println!("{} golden image(s) failed", failed);
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,536 @@
+use crate::config::Config;
+use crate::input::{self, Action};
+use crate::os_window::{self, OsWindow};
+use crate::timing::{FrameTimer, Timing};
+use crate::{gamepad, iced_sdl, pointer, replay, toolkit};
+use iced_wgpu::wgpu;
+use sdl2::event::{Event, WindowEvent};
+use std::sync::atomic::Ordering;
+
+/// What the host calls into to run the game around the toolkit. Everything has a default, so
+/// tools only need what they use.
+pub trait Game {
+    /// Called for every fixed step of `dt` seconds of simulation, which can be any number of
+    /// times a frame.
+    fn fixed_update(&mut self, _host: &mut SdlIcedHost, _dt: f64) {}
+
+    /// Called once a frame, after input has been handled, the toolkit updated and the simulation
+    /// stepped.
+    fn update(&mut self, _host: &mut SdlIcedHost, _timing: &Timing) {}
+
+    /// Draws the game under the toolkit in the main window. Returns whether anything was drawn,
+    /// otherwise the toolkit clears the frame itself.
+    fn render(&self, _view: &wgpu::TextureView, _encoder: &mut wgpu::CommandEncoder) -> bool {
+        false
+    }
+
+    /// Input of the main window that the toolkit didn't capture, along with whether it is a key
+    /// repeat.
+    fn input(&mut self, _event: &iced_core::Event, _repeat: bool) {}
+
+    /// Mouse motion while the mouse is captured in relative mode.
+    fn motion(&mut self, _dx: i32, _dy: i32) {}
+
+    /// A toolkit window of the main window was closed.
+    fn window_closed(&mut self) {}
+
+    /// A bound action was triggered outside of the toolkit.
+    fn action(&mut self, action: Action, _toolkit_open: bool) {
+        if action == Action::Quit {
+            crate::quit();
+        }
+    }
+}
+
+/// Draws the toolkit on top of the game, if given, or a black background otherwise.
+pub fn draw_frame(
+    toolkit: &mut toolkit::Toolkit<'_>,
+    engine: &mut iced_wgpu::Engine,
+    game: Option<&dyn Game>,
+    view: &wgpu::TextureView,
+    encoder: &mut wgpu::CommandEncoder,
+    format: wgpu::TextureFormat,
+) {
+    let drawn = game.is_some_and(|game| game.render(view, encoder));
+    let background = match drawn {
+        true => None,
+        false => Some(iced_core::Color::BLACK),
+    };
+    toolkit.draw(engine, view, encoder, format, background);
+}
+
+/// The graphics device, which everything that draws borrows from.
+pub struct Gpu {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    /// Format of the window surfaces, which everything drawing to them has to use.
+    pub format: wgpu::TextureFormat,
+    pub present_mode: wgpu::PresentMode,
+    pub alpha_mode: wgpu::CompositeAlphaMode,
+}
+
+/// Opens the device, asking for no more than the adapter can do.
+fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), String> {
+    // Software and GL adapters often can't do the defaults
+    let limits = match adapter.get_downlevel_capabilities().is_webgpu_compliant() {
+        true => wgpu::Limits::default(),
+        false => wgpu::Limits::downlevel_defaults(),
+    };
+    pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_limits: limits.using_resolution(adapter.limits()),
+            label: Some("device"),
+            required_features: wgpu::Features::empty(),
+            memory_hints: Default::default(),
+        },
+        None,
+    ))
+    .map_err(|e| format!("Failed to open '{}': {}", adapter.get_info().name, e))
+}
+
+/// Picks an sRGB format for the surface, as the toolkit and scene expect their colors to be
+/// converted on output. The common 8-bit ones go first, then any sRGB one, then whatever the
+/// surface prefers.
+fn surface_format(capabilities: &wgpu::SurfaceCapabilities) -> Option<wgpu::TextureFormat> {
+    let formats = &capabilities.formats;
+    [
+        wgpu::TextureFormat::Bgra8UnormSrgb,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ]
+    .into_iter()
+    .find(|f| formats.contains(f))
+    .or_else(|| formats.iter().copied().find(|f| f.is_srgb()))
+    .or_else(|| formats.first().copied())
+}
+
+/// Uses the preferred present mode if the surface supports it. Otherwise Immediate, which is only
+/// asked for to get rid of vsync, falls back on Mailbox, and everything else on vsync, which every
+/// surface has.
+fn present_mode(
+    capabilities: &wgpu::SurfaceCapabilities,
+    preferred: wgpu::PresentMode,
+) -> wgpu::PresentMode {
+    use wgpu::PresentMode;
+    let fallbacks: &[PresentMode] = match preferred {
+        // wgpu picks a supported mode for these itself
+        PresentMode::AutoVsync | PresentMode::AutoNoVsync => return preferred,
+        PresentMode::Immediate => &[PresentMode::Immediate, PresentMode::Mailbox],
+        // Mailbox doesn't tear, which Immediate would
+        PresentMode::Mailbox => &[PresentMode::Mailbox],
+        PresentMode::FifoRelaxed => &[PresentMode::FifoRelaxed],
+        PresentMode::Fifo => &[],
+    };
+    fallbacks
+        .iter()
+        .copied()
+        .find(|m| capabilities.present_modes.contains(m))
+        .unwrap_or(PresentMode::Fifo)
+}
+
+impl Gpu {
+    /// Sets up a device that can draw to `window`, returning it along with the window's surface.
+    /// Unless a backend is pinned, the primary backends are tried first, then GL, and then
+    /// whatever software adapter there is, so that machines without a proper GPU can still run.
+    pub fn new(
+        window: &sdl2::video::Window,
+        config: &Config,
+    ) -> Result<(Gpu, wgpu::Surface<'static>), String> {
+        let attempts = match config.backends {
+            Some(backends) => vec![(backends, false), (backends, true)],
+            None => vec![
+                (wgpu::Backends::PRIMARY, false),
+                (wgpu::Backends::GL, false),
+                (wgpu::Backends::all(), true),
+            ],
+        };
+        for (backends, fallback) in attempts {
+            // A pinned adapter is looked for by name, so there is nothing to fall back to
+            if fallback && config.adapter.is_some() {
+                continue;
+            }
+            // Failures are printed as they happen, as falling back is worth knowing about even
+            // when a later attempt works
+            match Gpu::try_new(window, config, backends, fallback) {
+                Ok(gpu) => return Ok(gpu),
+                Err(e) => println!("{}", e),
+            }
+        }
+        Err(String::from("No usable adapter found"))
+    }
+
+    fn try_new(
+        window: &sdl2::video::Window,
+        config: &Config,
+        backends: wgpu::Backends,
+        force_fallback_adapter: bool,
+    ) -> Result<(Gpu, wgpu::Surface<'static>), String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let surface = os_window::create_surface(&instance, window)?;
+        let adapter = match &config.adapter {
+            Some(name) => {
+                let name = name.to_lowercase();
+                instance
+                    .enumerate_adapters(backends)
+                    .into_iter()
+                    .find(|a| {
+                        a.get_info().name.to_lowercase().contains(&name)
+                            && a.is_surface_supported(&surface)
+                    })
+                    .ok_or_else(|| format!("No adapter named '{}' found on {:?}", name, backends))?
+            }
+            None => pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter,
+                compatible_surface: Some(&surface),
+            }))
+            .ok_or_else(|| match force_fallback_adapter {
+                true => format!("No software adapter found on {:?}", backends),
+                false => format!("No adapter found on {:?}", backends),
+            })?,
+        };
+
+        let info = adapter.get_info();
+        let (device, queue) = request_device(&adapter)?;
+        println!("Using adapter '{}' on {:?}", info.name, info.backend);
+
+        let capabilities = surface.get_capabilities(&adapter);
+        let format = surface_format(&capabilities)
+            .ok_or_else(|| format!("'{}' can't draw to the window", info.name))?;
+        let present_mode = present_mode(&capabilities, config.present_mode);
+        if present_mode != config.present_mode {
+            println!(
+                "Present mode {:?} isn't supported, using {:?}",
+                config.present_mode, present_mode
+            );
+        }
+        let alpha_mode = match capabilities
+            .alpha_modes
+            .contains(&wgpu::CompositeAlphaMode::Opaque)
+        {
+            true => wgpu::CompositeAlphaMode::Opaque,
+            false => wgpu::CompositeAlphaMode::Auto,
+        };
+
+        let gpu = Gpu {
+            instance,
+            adapter,
+            device,
+            queue,
+            format,
+            present_mode,
+            alpha_mode,
+        };
+        Ok((gpu, surface))
+    }
+
+    /// Sets up a device without any window, for rendering offscreen. Software adapters give the
+    /// same results everywhere, which is what golden images need.
+    pub fn headless(force_fallback_adapter: bool) -> Result<Gpu, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter,
+            compatible_surface: None,
+        }))
+        .ok_or_else(|| match force_fallback_adapter {
+            true => String::from("No software adapter found, is llvmpipe or lavapipe installed?"),
+            false => String::from("No adapter found"),
+        })?;
+        let (device, queue) = request_device(&adapter)?;
+        Ok(Gpu {
+            instance,
+            adapter,
+            device,
+            queue,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+        })
+    }
+
+    /// Prints every adapter on every backend.
+    pub fn list_adapters() {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        for adapter in instance.enumerate_adapters(wgpu::Backends::all()) {
+            let info = adapter.get_info();
+            println!("{}", info.name);
+            println!("    Backend: {:?}", info.backend);
+            println!("    Type: {:?}", info.device_type);
+            println!("    Driver: {} {}", info.driver, info.driver_info);
+            println!(
+                "    Vendor: {:#06x} Device: {:#06x}",
+                info.vendor, info.device
+            );
+        }
+    }
+}
+
+/// Runs SDL, the toolkit and the game together: owns the windows with their surfaces and
+/// toolkits, the iced engine, the clipboard and all the input handling.
+pub struct SdlIcedHost<'a> {
+    sdl_context: sdl2::Sdl,
+    video_subsystem: sdl2::VideoSubsystem,
+    gpu: &'a Gpu,
+    engine: iced_wgpu::Engine,
+    pub main_window: OsWindow<'a>,
+    // Editors, consoles and such that live in their own OS windows
+    extra_windows: Vec<OsWindow<'a>>,
+    clipboard: iced_sdl::Clipboard,
+    text_input: iced_sdl::TextInput,
+    mouse_cursor: iced_sdl::MouseCursor,
+    pointer: pointer::Pointer,
+    gamepads: gamepad::Gamepads,
+    forced_scale_factor: Option<f64>,
+    recorder: Option<replay::Recorder>,
+    replay: Option<replay::Replay>,
+    pub timer: FrameTimer,
+}
+
+impl<'a> SdlIcedHost<'a> {
+    pub fn new(
+        sdl_context: &sdl2::Sdl,
+        gpu: &'a Gpu,
+        window: sdl2::video::Window,
+        surface: wgpu::Surface<'static>,
+        config: &Config,
+    ) -> Result<SdlIcedHost<'a>, String> {
+        let video_subsystem = sdl_context.video()?;
+        let mut engine =
+            iced_wgpu::Engine::new(&gpu.adapter, &gpu.device, &gpu.queue, gpu.format, None);
+        let main_window = OsWindow::new(window, surface, gpu, &mut engine, config.scale_factor);
+        let mut timer = FrameTimer::new();
+        timer.fps_cap = config.fps_cap;
+        Ok(SdlIcedHost {
+            sdl_context: sdl_context.clone(),
+            clipboard: iced_sdl::Clipboard::from_video(&video_subsystem),
+            text_input: iced_sdl::TextInput::new(video_subsystem.text_input()),
+            mouse_cursor: iced_sdl::MouseCursor::new(),
+            pointer: pointer::Pointer::new(sdl_context.mouse()),
+            gamepads: gamepad::Gamepads::new(sdl_context.game_controller()?),
+            video_subsystem,
+            gpu,
+            engine,
+            main_window,
+            extra_windows: Vec::new(),
+            forced_scale_factor: config.scale_factor,
+            recorder: config
+                .record
+                .as_deref()
+                .map(replay::Recorder::new)
+                .transpose()?,
+            replay: config
+                .replay
+                .as_deref()
+                .map(replay::Replay::new)
+                .transpose()?,
+            timer,
+        })
+    }
+
+    /// Runs until the game quits or the main window is closed.
+    pub fn run(&mut self, game: &mut impl Game) -> Result<(), String> {
+        let mut event_pump = self.sdl_context.event_pump()?;
+        'running: loop {
+            let (recorded, events) = match self.replay.as_mut() {
+                Some(r) if !r.is_finished() => {
+                    // Live input is ignored while replaying, other than to quit
+                    if event_pump
+                        .poll_iter()
+                        .any(|e| matches!(e, Event::Quit { .. }))
+                    {
+                        break 'running;
+                    }
+                    r.next_frame()
+                }
+                _ => (None, event_pump.poll_iter().collect()),
+            };
+            let now = self.timer.begin_frame(recorded);
+            if let Some(r) = self.recorder.as_mut() {
+                r.record_frame(self.timer.timing().dt, &events);
+            }
+            for event in events {
+                match &event {
+                    Event::Window {
+                        window_id,
+                        win_event: WindowEvent::Close,
+                        ..
+                    } if *window_id != self.main_window.id() => {
+                        self.extra_windows.retain(|w| w.id() != *window_id);
+                        continue;
+                    }
+                    Event::Quit { .. }
+                    | Event::Window {
+                        win_event: WindowEvent::Close,
+                        ..
+                    } => {
+                        break 'running;
+                    }
+                    _ => (),
+                }
+
+                if let Some(nav) = self.gamepads.handle_event(&event) {
+                    let focus = self.sdl_context.keyboard().focused_window_id();
+                    self.find_window(focus).toolkit.navigate(nav);
+                }
+
+                // Controllers navigate the toolkit while it's open
+                if !self.main_window.toolkit.is_open() {
+                    let action = input::BINDINGS.lock().unwrap().controller_event(&event);
+                    if let Some(action) = action {
+                        game.action(action, false);
+                    }
+                }
+
+                if let Some((dx, dy)) = self.pointer.relative_motion(&event) {
+                    game.motion(dx, dy);
+                    continue;
+                }
+
+                self.find_window(event.get_window_id()).handle_event(&event);
+            }
+
+            // Lets the toolkit animate
+            self.main_window.toolkit.redraw(now);
+            for w in self.extra_windows.iter_mut() {
+                w.toolkit.redraw(now);
+            }
+
+            // Only input the toolkit didn't capture reaches the game
+            let windows = self.main_window.toolkit.window_count();
+            let events = self.main_window.update(&mut self.clipboard);
+            let toolkit_open = self.main_window.toolkit.is_open();
+            for (event, status, info) in events {
+                if status == iced_core::event::Status::Ignored {
+                    game.input(&event, info.repeat);
+                    // Holding a key down shouldn't trigger its action over and over
+                    if info.repeat {
+                        continue;
+                    }
+                    let action = input::BINDINGS.lock().unwrap().game_event(&event, info);
+                    if let Some(action) = action {
+                        game.action(action, toolkit_open);
+                    }
+                }
+            }
+            for w in self.extra_windows.iter_mut() {
+                w.update(&mut self.clipboard);
+            }
+            if self.main_window.toolkit.window_count() < windows {
+                game.window_closed();
+            }
+            // Extra windows go away with the last toolkit window in them
+            self.extra_windows.retain(|w| w.toolkit.is_open());
+            self.open_requested_windows();
+
+            self.pointer.update(
+                self.main_window.window_mut(),
+                crate::FLIGHT.load(Ordering::Relaxed),
+                self.main_window.toolkit.is_open(),
+            );
+            let focus = self.sdl_context.keyboard().focused_window_id();
+            let rect = self.find_window(focus).text_input_rect();
+            self.text_input.update(rect);
+            let focus = self.sdl_context.mouse().focused_window_id();
+            let interaction = self.find_window(focus).toolkit.mouse_interaction();
+            self.mouse_cursor.update(interaction);
+            if crate::QUIT.load(Ordering::Relaxed) {
+                break 'running;
+            }
+
+            let step = self.timer.fixed_step;
+            while self.timer.step() {
+                game.fixed_update(self, step);
+            }
+            let timing = self.timer.timing();
+            game.update(self, &timing);
+
+            // Nothing to see, so don't render or spin
+            if self.main_window.is_minimized()
+                && self.extra_windows.iter().all(|w| w.is_minimized())
+            {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                continue 'running;
+            }
+
+            self.main_window
+                .render(&mut self.engine, Some(&*game as &dyn Game));
+            for w in self.extra_windows.iter_mut() {
+                w.render(&mut self.engine, None);
+            }
+            self.timer.limit();
+
+            if crate::QUIT.load(Ordering::Relaxed) {
+                break 'running;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn open_requested_windows(&mut self) {
+        let requests: Vec<os_window::Request> =
+            os_window::REQUEST_QUEUE.lock().unwrap().drain(..).collect();
+        for r in requests {
+            let built = self
+                .video_subsystem
+                .window(&r.title, r.width, r.height)
+                .position_centered()
+                .resizable()
+                .allow_highdpi()
+                .build()
+                .map_err(|e| e.to_string())
+                .and_then(|window| {
+                    Ok((
+                        os_window::create_surface(&self.gpu.instance, &window)?,
+                        window,
+                    ))
+                });
+            match built {
+                Ok((surface, window)) => {
+                    let mut w = OsWindow::new(
+                        window,
+                        surface,
+                        self.gpu,
+                        &mut self.engine,
+                        self.forced_scale_factor,
+                    );
+                    w.toolkit.queue_message(r.message);
+                    self.extra_windows.push(w);
+                }
+                Err(e) => println!("Failed to open window '{}': {}", r.title, e),
+            }
+        }
+    }
+
+    /// Picks the window with the given SDL id, falling back to the main window for events that
+    /// aren't tied to one.
+    fn find_window(&mut self, window_id: Option<u32>) -> &mut OsWindow<'a> {
+        match window_id {
+            Some(id) => match self.extra_windows.iter_mut().find(|w| w.id() == id) {
+                Some(w) => w,
+                None => &mut self.main_window,
+            },
+            None => &mut self.main_window,
+        }
+    }
+
+    pub fn queue_message(&mut self, message: toolkit::Message) {
+        self.main_window.toolkit.queue_message(message);
+    }
+
+    /// Sets the cursor shown over the game, e.g. a crosshair while aiming.
+    pub fn set_game_cursor(&mut self, cursor: sdl2::mouse::SystemCursor) {
+        self.mouse_cursor.set_game_cursor(cursor);
+    }
+}
@@ -1,27 +1,71 @@
 use sdl2::event::{Event, WindowEvent};
 
-pub struct Clipboard(sdl2::clipboard::ClipboardUtil);
+pub enum Clipboard {
+    Sdl(sdl2::clipboard::ClipboardUtil),
+    /// Keeps the contents to ourselves, for when there is no system clipboard to talk to.
+    Memory {
+        standard: Option<String>,
+        primary: Option<String>,
+    },
+}
 impl Clipboard {
     pub fn new(clipboard: sdl2::clipboard::ClipboardUtil) -> Self {
-        Self(clipboard)
+        Self::Sdl(clipboard)
+    }
+
+    pub fn memory() -> Self {
+        Self::Memory {
+            standard: None,
+            primary: None,
+        }
+    }
+
+    /// Uses the system clipboard, unless the video driver has none (e.g., SDL's dummy driver).
+    pub fn from_video(video: &sdl2::VideoSubsystem) -> Self {
+        match video.current_video_driver() {
+            "dummy" | "offscreen" => Self::memory(),
+            _ => Self::new(video.clipboard()),
+        }
     }
 }
 impl iced_core::Clipboard for Clipboard {
     fn read(&self, kind: iced_core::clipboard::Kind) -> Option<String> {
-        match kind {
-            iced_core::clipboard::Kind::Standard => match self.0.has_clipboard_text() {
-                true => Some(self.0.clipboard_text().unwrap()),
-                false => None,
+        use iced_core::clipboard::Kind;
+        let text = match self {
+            Self::Sdl(c) => match kind {
+                Kind::Standard if c.has_clipboard_text() => c.clipboard_text(),
+                Kind::Primary if c.has_primary_selection_text() => c.primary_selection_text(),
+                _ => return None,
             },
-            iced_core::clipboard::Kind::Primary => match self.0.has_primary_selection_text() {
-                true => Some(self.0.primary_selection_text().unwrap()),
-                false => None,
+            Self::Memory { standard, primary } => {
+                return match kind {
+                    Kind::Standard => standard.clone(),
+                    Kind::Primary => primary.clone(),
+                }
+            }
+        };
+        text.map_err(|e| println!("Failed to read clipboard: {}", e))
+            .ok()
+    }
+    fn write(&mut self, kind: iced_core::clipboard::Kind, contents: String) {
+        use iced_core::clipboard::Kind;
+        let res = match self {
+            Self::Sdl(c) => match kind {
+                Kind::Standard => c.set_clipboard_text(contents.as_str()),
+                Kind::Primary => c.set_primary_selection_text(contents.as_str()),
             },
+            Self::Memory { standard, primary } => {
+                match kind {
+                    Kind::Standard => *standard = Some(contents),
+                    Kind::Primary => *primary = Some(contents),
+                }
+                Ok(())
+            }
+        };
+        if let Err(e) = res {
+            println!("Failed to write clipboard: {}", e);
         }
     }
-    fn write(&mut self, _kind: iced_core::clipboard::Kind, contents: String) {
-        self.0.set_clipboard_text(contents.as_str()).unwrap();
-    }
 }
 
 pub fn mouse_button(mouse_btn: &sdl2::mouse::MouseButton) -> iced::mouse::Button {
@@ -35,6 +79,106 @@ pub fn mouse_button(mouse_btn: &sdl2::mouse::MouseButton) -> iced::mouse::Button
     }
 }
 
+pub fn system_cursor(interaction: &iced_core::mouse::Interaction) -> sdl2::mouse::SystemCursor {
+    use iced_core::mouse::Interaction;
+    use sdl2::mouse::SystemCursor;
+
+    match interaction {
+        Interaction::None | Interaction::Idle => SystemCursor::Arrow,
+        Interaction::Pointer => SystemCursor::Hand,
+        Interaction::Grab => SystemCursor::Hand,
+        Interaction::Text => SystemCursor::IBeam,
+        Interaction::Crosshair => SystemCursor::Crosshair,
+        Interaction::Working => SystemCursor::WaitArrow,
+        Interaction::Grabbing => SystemCursor::SizeAll,
+        Interaction::ResizingHorizontally => SystemCursor::SizeWE,
+        Interaction::ResizingVertically => SystemCursor::SizeNS,
+        Interaction::ResizingDiagonallyUp => SystemCursor::SizeNESW,
+        Interaction::ResizingDiagonallyDown => SystemCursor::SizeNWSE,
+        Interaction::NotAllowed => SystemCursor::No,
+        Interaction::ZoomIn => SystemCursor::Crosshair,
+        Interaction::ZoomOut => SystemCursor::Crosshair,
+        Interaction::Cell => SystemCursor::Crosshair,
+        Interaction::Move => SystemCursor::SizeAll,
+        Interaction::Copy => SystemCursor::Arrow,
+        Interaction::Help => SystemCursor::Arrow,
+    }
+}
+
+/// Shows the system cursor matching the toolkit's mouse interaction, and the game's cursor when
+/// no window is open. Cursors are created once and only set when they change.
+pub struct MouseCursor {
+    cursors: std::collections::HashMap<sdl2::mouse::SystemCursor, sdl2::mouse::Cursor>,
+    game: sdl2::mouse::SystemCursor,
+    current: Option<sdl2::mouse::SystemCursor>,
+}
+impl Default for MouseCursor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MouseCursor {
+    pub fn new() -> Self {
+        Self {
+            cursors: std::collections::HashMap::new(),
+            game: sdl2::mouse::SystemCursor::Arrow,
+            current: None,
+        }
+    }
+
+    /// Sets the cursor shown when no window is open. It is applied on the next update.
+    pub fn set_game_cursor(&mut self, cursor: sdl2::mouse::SystemCursor) {
+        self.game = cursor;
+    }
+
+    pub fn update(&mut self, interaction: Option<iced_core::mouse::Interaction>) {
+        use std::collections::hash_map::Entry;
+        let cursor = interaction.map_or(self.game, |i| system_cursor(&i));
+        if self.current == Some(cursor) {
+            return;
+        }
+        let c = match self.cursors.entry(cursor) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => match sdl2::mouse::Cursor::from_system(cursor) {
+                Ok(c) => e.insert(c),
+                Err(err) => {
+                    println!("Failed to create cursor {:?}: {}", cursor, err);
+                    return;
+                }
+            },
+        };
+        c.set();
+        self.current = Some(cursor);
+    }
+}
+
+/// Pixels scrolled per wheel notch when SDL reports a precise (trackpad) delta. Matches the
+/// line height `scrollable` uses, so both kinds of device scroll at the same speed.
+const WHEEL_PIXELS_PER_LINE: f32 = 60.0;
+
+pub fn scroll_delta(
+    x: f32,
+    y: f32,
+    direction: &sdl2::mouse::MouseWheelDirection,
+) -> iced_core::mouse::ScrollDelta {
+    use iced_core::mouse::ScrollDelta;
+    // SDL's horizontal axis is inverted with respect to iced's
+    let (x, y) = match direction {
+        sdl2::mouse::MouseWheelDirection::Flipped => (x, -y),
+        _ => (-x, y),
+    };
+    // Notched wheels report whole steps, anything fractional comes from a trackpad
+    if x.fract() == 0.0 && y.fract() == 0.0 {
+        ScrollDelta::Lines { x, y }
+    } else {
+        ScrollDelta::Pixels {
+            x: x * WHEEL_PIXELS_PER_LINE,
+            y: y * WHEEL_PIXELS_PER_LINE,
+        }
+    }
+}
+
 pub fn key(keycode: &sdl2::keyboard::Keycode) -> iced::keyboard::key::Key {
     use iced::keyboard::key::Key::{Character, Named};
     use iced::keyboard::key::Named as NamedKey;
@@ -47,21 +191,164 @@ pub fn key(keycode: &sdl2::keyboard::Keycode) -> iced::keyboard::key::Key {
         Keycode::BACKSPACE => Named(NamedKey::Backspace),
         Keycode::TAB => Named(NamedKey::Tab),
         Keycode::SPACE => Named(NamedKey::Space),
-        //Keycode::EXCLAIM => Named(NamedKey::Exclaim),
-        //Keycode::QUOTEDBL => Named(NamedKey::Tab),
-        //Keycode::HASH => Named(NamedKey::Tab),
-        //Keycode::DOLLAR => Named(NamedKey::Tab),
-        //Keycode::PERCENT => Named(NamedKey::Tab),
-        //Keycode::AMPERSAND => Named(NamedKey::Tab),
-        //Keycode::QUOTE => Named(NamedKey::Tab),
-        //Keycode::LEFTPAREN => Named(NamedKey::Tab),
-        //Keycode::RIGHTPAREN => Named(NamedKey::Tab),
-        //Keycode::ASTERISK => Named(NamedKey::Tab),
-        //Keycode::PLUS => Named(NamedKey::Tab),
-        //Keycode::COMMA => Named(NamedKey::Tab),
-        //Keycode::MINUS => Named(NamedKey::Tab),
-        //Keycode::PERIOD => Named(NamedKey::Tab),
-        //Keycode::SLASH => Named(NamedKey::Tab),
+        Keycode::DELETE => Named(NamedKey::Delete),
+        Keycode::CAPSLOCK => Named(NamedKey::CapsLock),
+        Keycode::F1 => Named(NamedKey::F1),
+        Keycode::F2 => Named(NamedKey::F2),
+        Keycode::F3 => Named(NamedKey::F3),
+        Keycode::F4 => Named(NamedKey::F4),
+        Keycode::F5 => Named(NamedKey::F5),
+        Keycode::F6 => Named(NamedKey::F6),
+        Keycode::F7 => Named(NamedKey::F7),
+        Keycode::F8 => Named(NamedKey::F8),
+        Keycode::F9 => Named(NamedKey::F9),
+        Keycode::F10 => Named(NamedKey::F10),
+        Keycode::F11 => Named(NamedKey::F11),
+        Keycode::F12 => Named(NamedKey::F12),
+        Keycode::F13 => Named(NamedKey::F13),
+        Keycode::F14 => Named(NamedKey::F14),
+        Keycode::F15 => Named(NamedKey::F15),
+        Keycode::F16 => Named(NamedKey::F16),
+        Keycode::F17 => Named(NamedKey::F17),
+        Keycode::F18 => Named(NamedKey::F18),
+        Keycode::F19 => Named(NamedKey::F19),
+        Keycode::F20 => Named(NamedKey::F20),
+        Keycode::F21 => Named(NamedKey::F21),
+        Keycode::F22 => Named(NamedKey::F22),
+        Keycode::F23 => Named(NamedKey::F23),
+        Keycode::F24 => Named(NamedKey::F24),
+        Keycode::PRINTSCREEN => Named(NamedKey::PrintScreen),
+        Keycode::SCROLLLOCK => Named(NamedKey::ScrollLock),
+        Keycode::PAUSE => Named(NamedKey::Pause),
+        Keycode::INSERT => Named(NamedKey::Insert),
+        Keycode::HOME => Named(NamedKey::Home),
+        Keycode::PAGEUP => Named(NamedKey::PageUp),
+        Keycode::END => Named(NamedKey::End),
+        Keycode::PAGEDOWN => Named(NamedKey::PageDown),
+        Keycode::RIGHT => Named(NamedKey::ArrowRight),
+        Keycode::LEFT => Named(NamedKey::ArrowLeft),
+        Keycode::DOWN => Named(NamedKey::ArrowDown),
+        Keycode::UP => Named(NamedKey::ArrowUp),
+        Keycode::NUMLOCKCLEAR => Named(NamedKey::NumLock),
+        Keycode::APPLICATION => Named(NamedKey::ContextMenu),
+        Keycode::POWER => Named(NamedKey::Power),
+        Keycode::EXECUTE => Named(NamedKey::Execute),
+        Keycode::HELP => Named(NamedKey::Help),
+        Keycode::MENU => Named(NamedKey::ContextMenu),
+        Keycode::SELECT => Named(NamedKey::Select),
+        Keycode::STOP => Named(NamedKey::MediaStop),
+        Keycode::AGAIN => Named(NamedKey::Again),
+        Keycode::UNDO => Named(NamedKey::Undo),
+        Keycode::CUT => Named(NamedKey::Cut),
+        Keycode::COPY => Named(NamedKey::Copy),
+        Keycode::PASTE => Named(NamedKey::Paste),
+        Keycode::FIND => Named(NamedKey::Find),
+        Keycode::MUTE => Named(NamedKey::AudioVolumeMute),
+        Keycode::VOLUMEUP => Named(NamedKey::AudioVolumeUp),
+        Keycode::VOLUMEDOWN => Named(NamedKey::AudioVolumeDown),
+        Keycode::ALTERASE => Named(NamedKey::EraseEof),
+        Keycode::CANCEL => Named(NamedKey::Cancel),
+        Keycode::CLEAR => Named(NamedKey::Clear),
+        Keycode::PRIOR => Named(NamedKey::PageUp),
+        Keycode::RETURN2 => Named(NamedKey::Enter),
+        Keycode::CRSEL => Named(NamedKey::CrSel),
+        Keycode::EXSEL => Named(NamedKey::ExSel),
+        Keycode::LCTRL => Named(NamedKey::Control),
+        Keycode::LSHIFT => Named(NamedKey::Shift),
+        Keycode::LALT => Named(NamedKey::Alt),
+        Keycode::LGUI => Named(NamedKey::Super),
+        Keycode::RCTRL => Named(NamedKey::Control),
+        Keycode::RSHIFT => Named(NamedKey::Shift),
+        Keycode::RALT => Named(NamedKey::Alt),
+        Keycode::RGUI => Named(NamedKey::Super),
+        Keycode::MODE => Named(NamedKey::ModeChange),
+        Keycode::AUDIONEXT => Named(NamedKey::MediaTrackNext),
+        Keycode::AUDIOPREV => Named(NamedKey::MediaTrackPrevious),
+        Keycode::AUDIOSTOP => Named(NamedKey::MediaStop),
+        Keycode::AUDIOPLAY => Named(NamedKey::MediaPlayPause),
+        Keycode::AUDIOMUTE => Named(NamedKey::AudioVolumeMute),
+        Keycode::MEDIASELECT => Named(NamedKey::LaunchMediaPlayer),
+        Keycode::WWW => Named(NamedKey::LaunchWebBrowser),
+        Keycode::MAIL => Named(NamedKey::LaunchMail),
+        Keycode::CALCULATOR => Named(NamedKey::LaunchApplication2),
+        Keycode::COMPUTER => Named(NamedKey::LaunchApplication1),
+        Keycode::AC_SEARCH => Named(NamedKey::BrowserSearch),
+        Keycode::AC_HOME => Named(NamedKey::BrowserHome),
+        Keycode::AC_BACK => Named(NamedKey::BrowserBack),
+        Keycode::AC_FORWARD => Named(NamedKey::BrowserForward),
+        Keycode::AC_STOP => Named(NamedKey::BrowserStop),
+        Keycode::AC_REFRESH => Named(NamedKey::BrowserRefresh),
+        Keycode::AC_BOOKMARKS => Named(NamedKey::BrowserFavorites),
+        Keycode::BRIGHTNESSDOWN => Named(NamedKey::BrightnessDown),
+        Keycode::BRIGHTNESSUP => Named(NamedKey::BrightnessUp),
+        Keycode::DISPLAYSWITCH => Named(NamedKey::DisplaySwap),
+        Keycode::EJECT => Named(NamedKey::Eject),
+        Keycode::SLEEP => Named(NamedKey::Standby),
+        // Keypad
+        Keycode::KP_ENTER => Named(NamedKey::Enter),
+        Keycode::KP_TAB => Named(NamedKey::Tab),
+        Keycode::KP_BACKSPACE => Named(NamedKey::Backspace),
+        Keycode::KP_SPACE => Named(NamedKey::Space),
+        Keycode::KP_CLEAR => Named(NamedKey::Clear),
+        Keycode::KP_CLEARENTRY => Named(NamedKey::Clear),
+        Keycode::KP_DIVIDE => Character(SmolStr::new("/")),
+        Keycode::KP_MULTIPLY => Character(SmolStr::new("*")),
+        Keycode::KP_MINUS => Character(SmolStr::new("-")),
+        Keycode::KP_PLUS => Character(SmolStr::new("+")),
+        Keycode::KP_1 => Character(SmolStr::new("1")),
+        Keycode::KP_2 => Character(SmolStr::new("2")),
+        Keycode::KP_3 => Character(SmolStr::new("3")),
+        Keycode::KP_4 => Character(SmolStr::new("4")),
+        Keycode::KP_5 => Character(SmolStr::new("5")),
+        Keycode::KP_6 => Character(SmolStr::new("6")),
+        Keycode::KP_7 => Character(SmolStr::new("7")),
+        Keycode::KP_8 => Character(SmolStr::new("8")),
+        Keycode::KP_9 => Character(SmolStr::new("9")),
+        Keycode::KP_0 => Character(SmolStr::new("0")),
+        Keycode::KP_PERIOD => Character(SmolStr::new(".")),
+        Keycode::KP_EQUALS => Character(SmolStr::new("=")),
+        Keycode::KP_COMMA => Character(SmolStr::new(",")),
+        Keycode::KP_EQUALSAS400 => Character(SmolStr::new("=")),
+        Keycode::KP_00 => Character(SmolStr::new("00")),
+        Keycode::KP_000 => Character(SmolStr::new("000")),
+        Keycode::KP_LEFTPAREN => Character(SmolStr::new("(")),
+        Keycode::KP_RIGHTPAREN => Character(SmolStr::new(")")),
+        Keycode::KP_LEFTBRACE => Character(SmolStr::new("{")),
+        Keycode::KP_RIGHTBRACE => Character(SmolStr::new("}")),
+        Keycode::KP_A => Character(SmolStr::new("a")),
+        Keycode::KP_B => Character(SmolStr::new("b")),
+        Keycode::KP_C => Character(SmolStr::new("c")),
+        Keycode::KP_D => Character(SmolStr::new("d")),
+        Keycode::KP_E => Character(SmolStr::new("e")),
+        Keycode::KP_F => Character(SmolStr::new("f")),
+        Keycode::KP_XOR => Character(SmolStr::new("^")),
+        Keycode::KP_PERCENT => Character(SmolStr::new("%")),
+        Keycode::KP_LESS => Character(SmolStr::new("<")),
+        Keycode::KP_GREATER => Character(SmolStr::new(">")),
+        Keycode::KP_AMPERSAND => Character(SmolStr::new("&")),
+        Keycode::KP_DBLAMPERSAND => Character(SmolStr::new("&&")),
+        Keycode::KP_VERTICALBAR => Character(SmolStr::new("|")),
+        Keycode::KP_DBLVERTICALBAR => Character(SmolStr::new("||")),
+        Keycode::KP_COLON => Character(SmolStr::new(":")),
+        Keycode::KP_HASH => Character(SmolStr::new("#")),
+        Keycode::KP_AT => Character(SmolStr::new("@")),
+        Keycode::KP_EXCLAM => Character(SmolStr::new("!")),
+        // Printable characters
+        Keycode::EXCLAIM => Character(SmolStr::new("!")),
+        Keycode::QUOTEDBL => Character(SmolStr::new("\"")),
+        Keycode::HASH => Character(SmolStr::new("#")),
+        Keycode::DOLLAR => Character(SmolStr::new("$")),
+        Keycode::PERCENT => Character(SmolStr::new("%")),
+        Keycode::AMPERSAND => Character(SmolStr::new("&")),
+        Keycode::QUOTE => Character(SmolStr::new("'")),
+        Keycode::LEFTPAREN => Character(SmolStr::new("(")),
+        Keycode::RIGHTPAREN => Character(SmolStr::new(")")),
+        Keycode::ASTERISK => Character(SmolStr::new("*")),
+        Keycode::PLUS => Character(SmolStr::new("+")),
+        Keycode::COMMA => Character(SmolStr::new(",")),
+        Keycode::MINUS => Character(SmolStr::new("-")),
+        Keycode::PERIOD => Character(SmolStr::new(".")),
+        Keycode::SLASH => Character(SmolStr::new("/")),
         Keycode::NUM_0 => Character(SmolStr::new("0")),
         Keycode::NUM_1 => Character(SmolStr::new("1")),
         Keycode::NUM_2 => Character(SmolStr::new("2")),
@@ -72,7 +359,19 @@ pub fn key(keycode: &sdl2::keyboard::Keycode) -> iced::keyboard::key::Key {
         Keycode::NUM_7 => Character(SmolStr::new("7")),
         Keycode::NUM_8 => Character(SmolStr::new("8")),
         Keycode::NUM_9 => Character(SmolStr::new("9")),
-        //Keycode:: => Named(NamedKey::Tab),
+        Keycode::COLON => Character(SmolStr::new(":")),
+        Keycode::SEMICOLON => Character(SmolStr::new(";")),
+        Keycode::LESS => Character(SmolStr::new("<")),
+        Keycode::EQUALS => Character(SmolStr::new("=")),
+        Keycode::GREATER => Character(SmolStr::new(">")),
+        Keycode::QUESTION => Character(SmolStr::new("?")),
+        Keycode::AT => Character(SmolStr::new("@")),
+        Keycode::LEFTBRACKET => Character(SmolStr::new("[")),
+        Keycode::BACKSLASH => Character(SmolStr::new("\\")),
+        Keycode::RIGHTBRACKET => Character(SmolStr::new("]")),
+        Keycode::CARET => Character(SmolStr::new("^")),
+        Keycode::UNDERSCORE => Character(SmolStr::new("_")),
+        Keycode::BACKQUOTE => Character(SmolStr::new("`")),
         Keycode::A => Character(SmolStr::new("a")),
         Keycode::B => Character(SmolStr::new("b")),
         Keycode::C => Character(SmolStr::new("c")),
@@ -103,13 +402,276 @@ pub fn key(keycode: &sdl2::keyboard::Keycode) -> iced::keyboard::key::Key {
     }
 }
 
+pub fn location(keycode: &sdl2::keyboard::Keycode) -> iced::keyboard::Location {
+    use iced::keyboard::Location;
+    use sdl2::keyboard::Keycode;
+
+    match *keycode {
+        Keycode::LCTRL | Keycode::LSHIFT | Keycode::LALT | Keycode::LGUI => Location::Left,
+        Keycode::RCTRL | Keycode::RSHIFT | Keycode::RALT | Keycode::RGUI => Location::Right,
+        Keycode::NUMLOCKCLEAR
+        | Keycode::KP_ENTER
+        | Keycode::KP_TAB
+        | Keycode::KP_BACKSPACE
+        | Keycode::KP_SPACE
+        | Keycode::KP_CLEAR
+        | Keycode::KP_CLEARENTRY
+        | Keycode::KP_DIVIDE
+        | Keycode::KP_MULTIPLY
+        | Keycode::KP_MINUS
+        | Keycode::KP_PLUS
+        | Keycode::KP_1
+        | Keycode::KP_2
+        | Keycode::KP_3
+        | Keycode::KP_4
+        | Keycode::KP_5
+        | Keycode::KP_6
+        | Keycode::KP_7
+        | Keycode::KP_8
+        | Keycode::KP_9
+        | Keycode::KP_0
+        | Keycode::KP_PERIOD
+        | Keycode::KP_EQUALS
+        | Keycode::KP_COMMA
+        | Keycode::KP_EQUALSAS400
+        | Keycode::KP_00
+        | Keycode::KP_000
+        | Keycode::KP_LEFTPAREN
+        | Keycode::KP_RIGHTPAREN
+        | Keycode::KP_LEFTBRACE
+        | Keycode::KP_RIGHTBRACE
+        | Keycode::KP_A
+        | Keycode::KP_B
+        | Keycode::KP_C
+        | Keycode::KP_D
+        | Keycode::KP_E
+        | Keycode::KP_F
+        | Keycode::KP_XOR
+        | Keycode::KP_PERCENT
+        | Keycode::KP_LESS
+        | Keycode::KP_GREATER
+        | Keycode::KP_AMPERSAND
+        | Keycode::KP_DBLAMPERSAND
+        | Keycode::KP_VERTICALBAR
+        | Keycode::KP_DBLVERTICALBAR
+        | Keycode::KP_COLON
+        | Keycode::KP_HASH
+        | Keycode::KP_AT
+        | Keycode::KP_EXCLAM
+        | Keycode::KP_POWER
+        | Keycode::KP_MEMSTORE
+        | Keycode::KP_MEMRECALL
+        | Keycode::KP_MEMCLEAR
+        | Keycode::KP_MEMADD
+        | Keycode::KP_MEMSUBTRACT
+        | Keycode::KP_MEMMULTIPLY
+        | Keycode::KP_MEMDIVIDE
+        | Keycode::KP_PLUSMINUS
+        | Keycode::KP_BINARY
+        | Keycode::KP_OCTAL
+        | Keycode::KP_DECIMAL
+        | Keycode::KP_HEXADECIMAL => Location::Numpad,
+        _ => Location::Standard,
+    }
+}
+
+/// Maps a scancode to the key at that position. Scancodes without a `Code` keep their raw value
+/// in `NativeCode::Android`, see [`native_scancode`].
 pub fn physical_key(scancode: &sdl2::keyboard::Scancode) -> iced::keyboard::key::Physical {
     use iced_core::keyboard::key::{Code, NativeCode, Physical};
     use sdl2::keyboard::Scancode;
 
-    match &scancode {
-        Scancode::A => Physical::Code(Code::KeyA),
-        _ => Physical::Unidentified(NativeCode::Unidentified),
+    let code = match scancode {
+        Scancode::A => Code::KeyA,
+        Scancode::B => Code::KeyB,
+        Scancode::C => Code::KeyC,
+        Scancode::D => Code::KeyD,
+        Scancode::E => Code::KeyE,
+        Scancode::F => Code::KeyF,
+        Scancode::G => Code::KeyG,
+        Scancode::H => Code::KeyH,
+        Scancode::I => Code::KeyI,
+        Scancode::J => Code::KeyJ,
+        Scancode::K => Code::KeyK,
+        Scancode::L => Code::KeyL,
+        Scancode::M => Code::KeyM,
+        Scancode::N => Code::KeyN,
+        Scancode::O => Code::KeyO,
+        Scancode::P => Code::KeyP,
+        Scancode::Q => Code::KeyQ,
+        Scancode::R => Code::KeyR,
+        Scancode::S => Code::KeyS,
+        Scancode::T => Code::KeyT,
+        Scancode::U => Code::KeyU,
+        Scancode::V => Code::KeyV,
+        Scancode::W => Code::KeyW,
+        Scancode::X => Code::KeyX,
+        Scancode::Y => Code::KeyY,
+        Scancode::Z => Code::KeyZ,
+        Scancode::Num1 => Code::Digit1,
+        Scancode::Num2 => Code::Digit2,
+        Scancode::Num3 => Code::Digit3,
+        Scancode::Num4 => Code::Digit4,
+        Scancode::Num5 => Code::Digit5,
+        Scancode::Num6 => Code::Digit6,
+        Scancode::Num7 => Code::Digit7,
+        Scancode::Num8 => Code::Digit8,
+        Scancode::Num9 => Code::Digit9,
+        Scancode::Num0 => Code::Digit0,
+        Scancode::Return => Code::Enter,
+        Scancode::Escape => Code::Escape,
+        Scancode::Backspace => Code::Backspace,
+        Scancode::Tab => Code::Tab,
+        Scancode::Space => Code::Space,
+        Scancode::Minus => Code::Minus,
+        Scancode::Equals => Code::Equal,
+        Scancode::LeftBracket => Code::BracketLeft,
+        Scancode::RightBracket => Code::BracketRight,
+        Scancode::Backslash => Code::Backslash,
+        Scancode::NonUsHash => Code::Backslash,
+        Scancode::Semicolon => Code::Semicolon,
+        Scancode::Apostrophe => Code::Quote,
+        Scancode::Grave => Code::Backquote,
+        Scancode::Comma => Code::Comma,
+        Scancode::Period => Code::Period,
+        Scancode::Slash => Code::Slash,
+        Scancode::CapsLock => Code::CapsLock,
+        Scancode::F1 => Code::F1,
+        Scancode::F2 => Code::F2,
+        Scancode::F3 => Code::F3,
+        Scancode::F4 => Code::F4,
+        Scancode::F5 => Code::F5,
+        Scancode::F6 => Code::F6,
+        Scancode::F7 => Code::F7,
+        Scancode::F8 => Code::F8,
+        Scancode::F9 => Code::F9,
+        Scancode::F10 => Code::F10,
+        Scancode::F11 => Code::F11,
+        Scancode::F12 => Code::F12,
+        Scancode::PrintScreen => Code::PrintScreen,
+        Scancode::ScrollLock => Code::ScrollLock,
+        Scancode::Pause => Code::Pause,
+        Scancode::Insert => Code::Insert,
+        Scancode::Home => Code::Home,
+        Scancode::PageUp => Code::PageUp,
+        Scancode::Delete => Code::Delete,
+        Scancode::End => Code::End,
+        Scancode::PageDown => Code::PageDown,
+        Scancode::Right => Code::ArrowRight,
+        Scancode::Left => Code::ArrowLeft,
+        Scancode::Down => Code::ArrowDown,
+        Scancode::Up => Code::ArrowUp,
+        Scancode::NumLockClear => Code::NumLock,
+        Scancode::KpDivide => Code::NumpadDivide,
+        Scancode::KpMultiply => Code::NumpadMultiply,
+        Scancode::KpMinus => Code::NumpadSubtract,
+        Scancode::KpPlus => Code::NumpadAdd,
+        Scancode::KpEnter => Code::NumpadEnter,
+        Scancode::Kp1 => Code::Numpad1,
+        Scancode::Kp2 => Code::Numpad2,
+        Scancode::Kp3 => Code::Numpad3,
+        Scancode::Kp4 => Code::Numpad4,
+        Scancode::Kp5 => Code::Numpad5,
+        Scancode::Kp6 => Code::Numpad6,
+        Scancode::Kp7 => Code::Numpad7,
+        Scancode::Kp8 => Code::Numpad8,
+        Scancode::Kp9 => Code::Numpad9,
+        Scancode::Kp0 => Code::Numpad0,
+        Scancode::KpPeriod => Code::NumpadDecimal,
+        Scancode::NonUsBackslash => Code::IntlBackslash,
+        Scancode::Application => Code::ContextMenu,
+        Scancode::Power => Code::Power,
+        Scancode::KpEquals => Code::NumpadEqual,
+        Scancode::F13 => Code::F13,
+        Scancode::F14 => Code::F14,
+        Scancode::F15 => Code::F15,
+        Scancode::F16 => Code::F16,
+        Scancode::F17 => Code::F17,
+        Scancode::F18 => Code::F18,
+        Scancode::F19 => Code::F19,
+        Scancode::F20 => Code::F20,
+        Scancode::F21 => Code::F21,
+        Scancode::F22 => Code::F22,
+        Scancode::F23 => Code::F23,
+        Scancode::F24 => Code::F24,
+        Scancode::Help => Code::Help,
+        Scancode::Menu => Code::ContextMenu,
+        Scancode::Select => Code::Select,
+        Scancode::Again => Code::Again,
+        Scancode::Undo => Code::Undo,
+        Scancode::Cut => Code::Cut,
+        Scancode::Copy => Code::Copy,
+        Scancode::Paste => Code::Paste,
+        Scancode::Find => Code::Find,
+        Scancode::Mute => Code::AudioVolumeMute,
+        Scancode::VolumeUp => Code::AudioVolumeUp,
+        Scancode::VolumeDown => Code::AudioVolumeDown,
+        Scancode::KpComma => Code::NumpadComma,
+        Scancode::KpEqualsAS400 => Code::NumpadEqual,
+        Scancode::International1 => Code::IntlRo,
+        Scancode::International2 => Code::KanaMode,
+        Scancode::International3 => Code::IntlYen,
+        Scancode::International4 => Code::Convert,
+        Scancode::International5 => Code::NonConvert,
+        Scancode::Lang1 => Code::Lang1,
+        Scancode::Lang2 => Code::Lang2,
+        Scancode::Lang3 => Code::Lang3,
+        Scancode::Lang4 => Code::Lang4,
+        Scancode::Lang5 => Code::Lang5,
+        Scancode::KpLeftParen => Code::NumpadParenLeft,
+        Scancode::KpRightParen => Code::NumpadParenRight,
+        Scancode::KpBackspace => Code::NumpadBackspace,
+        Scancode::KpHash => Code::NumpadHash,
+        Scancode::KpMemStore => Code::NumpadMemoryStore,
+        Scancode::KpMemRecall => Code::NumpadMemoryRecall,
+        Scancode::KpMemClear => Code::NumpadMemoryClear,
+        Scancode::KpMemAdd => Code::NumpadMemoryAdd,
+        Scancode::KpMemSubtract => Code::NumpadMemorySubtract,
+        Scancode::KpClear => Code::NumpadClear,
+        Scancode::KpClearEntry => Code::NumpadClearEntry,
+        Scancode::LCtrl => Code::ControlLeft,
+        Scancode::LShift => Code::ShiftLeft,
+        Scancode::LAlt => Code::AltLeft,
+        Scancode::LGui => Code::SuperLeft,
+        Scancode::RCtrl => Code::ControlRight,
+        Scancode::RShift => Code::ShiftRight,
+        Scancode::RAlt => Code::AltRight,
+        Scancode::RGui => Code::SuperRight,
+        Scancode::AudioNext => Code::MediaTrackNext,
+        Scancode::AudioPrev => Code::MediaTrackPrevious,
+        Scancode::AudioStop => Code::MediaStop,
+        Scancode::AudioPlay => Code::MediaPlayPause,
+        Scancode::AudioMute => Code::AudioVolumeMute,
+        Scancode::MediaSelect => Code::MediaSelect,
+        Scancode::Mail => Code::LaunchMail,
+        Scancode::Calculator => Code::LaunchApp2,
+        Scancode::Computer => Code::LaunchApp1,
+        Scancode::AcSearch => Code::BrowserSearch,
+        Scancode::AcHome => Code::BrowserHome,
+        Scancode::AcBack => Code::BrowserBack,
+        Scancode::AcForward => Code::BrowserForward,
+        Scancode::AcStop => Code::BrowserStop,
+        Scancode::AcRefresh => Code::BrowserRefresh,
+        Scancode::AcBookmarks => Code::BrowserFavorites,
+        Scancode::Eject => Code::Eject,
+        Scancode::Sleep => Code::Sleep,
+        // iced has no slot for SDL scancodes, so they go in the Android one, which is otherwise
+        // unused as all our input comes from SDL
+        _ => return Physical::Unidentified(NativeCode::Android(*scancode as u32)),
+    };
+    Physical::Code(code)
+}
+
+/// The raw scancode of a key [`physical_key`] has no `Code` for.
+pub fn native_scancode(
+    physical: &iced::keyboard::key::Physical,
+) -> Option<sdl2::keyboard::Scancode> {
+    use iced_core::keyboard::key::{NativeCode, Physical};
+    match physical {
+        Physical::Unidentified(NativeCode::Android(code)) => {
+            sdl2::keyboard::Scancode::from_i32(*code as i32)
+        }
+        _ => None,
     }
 }
 
@@ -117,33 +679,324 @@ pub fn modifier(keymod: &sdl2::keyboard::Mod) -> iced::keyboard::Modifiers {
     use iced::keyboard::Modifiers;
     use sdl2::keyboard::Mod;
     let mut modifiers = iced_core::keyboard::Modifiers::empty();
-    if keymod.contains(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
         modifiers.insert(Modifiers::SHIFT);
     }
-    if keymod.contains(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+    if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
         modifiers.insert(Modifiers::CTRL);
     }
-    if keymod.contains(Mod::LALTMOD | Mod::RALTMOD) {
+    if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) {
         modifiers.insert(Modifiers::ALT);
     }
-    if keymod.contains(Mod::LGUIMOD | Mod::RGUIMOD) {
+    if keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD) {
         modifiers.insert(Modifiers::LOGO);
     }
     modifiers
 }
 
-pub fn window_event(event: &Event, scale_factor: f64) -> Option<iced_core::Event> {
+/// Turns SDL text input (and with it the IME and on-screen keyboards) on only while a text input
+/// has focus, and keeps the candidate window next to it.
+pub struct TextInput {
+    util: sdl2::keyboard::TextInputUtil,
+    rect: Option<sdl2::rect::Rect>,
+}
+impl TextInput {
+    pub fn new(util: sdl2::keyboard::TextInputUtil) -> Self {
+        // SDL starts with text input enabled
+        util.stop();
+        Self { util, rect: None }
+    }
+
+    pub fn update(&mut self, bounds: Option<iced_core::Rectangle>) {
+        let rect = bounds.map(|b| {
+            sdl2::rect::Rect::new(b.x as i32, b.y as i32, b.width as u32, b.height as u32)
+        });
+        if rect == self.rect {
+            return;
+        }
+        match rect {
+            Some(r) => {
+                if self.rect.is_none() {
+                    self.util.start();
+                }
+                self.util.set_rect(r);
+            }
+            None => self.util.stop(),
+        }
+        self.rect = rect;
+    }
+}
+
+/// What SDL tells about an event that iced has no place for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EventInfo {
+    pub repeat: bool,
+    /// The key that was pressed. Bindings are matched on it, as iced gives keypad keys the same
+    /// key as those on the main row, and some keys none at all.
+    pub keycode: Option<sdl2::keyboard::Keycode>,
+}
+
+/// Keeps track of the input state that SDL spreads across several events, so that every
+/// translated event carries the full picture iced expects.
+pub struct Adapter {
+    modifiers: iced_core::keyboard::Modifiers,
+    preedit: Option<String>,
+    size: iced_core::Size,
+    fingers: std::collections::HashMap<(i64, i64), u64>,
+    next_finger: u64,
+    // The held back key press
+    pending_key: Option<(iced_core::Event, EventInfo)>,
+}
+impl Adapter {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            modifiers: iced_core::keyboard::Modifiers::empty(),
+            preedit: None,
+            size: iced_core::Size::new(width as f32, height as f32),
+            fingers: std::collections::HashMap::new(),
+            next_finger: 0,
+            pending_key: None,
+        }
+    }
+
+    /// Hands out the last key press if it is still waiting for its text. Should be called once
+    /// all the events of a frame have been processed.
+    pub fn flush(&mut self) -> Option<(iced_core::Event, EventInfo)> {
+        self.pending_key.take()
+    }
+
+    pub fn modifiers(&self) -> iced_core::keyboard::Modifiers {
+        self.modifiers
+    }
+
+    /// Text currently being composed by the input method, which iced has no event for.
+    pub fn preedit(&self) -> Option<&str> {
+        self.preedit.as_deref()
+    }
+
+    /// SDL finger ids are only unique per touch device, so we hand out our own for as long as
+    /// the finger stays down.
+    fn finger(&mut self, touch_id: i64, finger_id: i64) -> iced_core::touch::Finger {
+        let next = &mut self.next_finger;
+        let id = *self
+            .fingers
+            .entry((touch_id, finger_id))
+            .or_insert_with(|| {
+                *next += 1;
+                *next
+            });
+        iced_core::touch::Finger(id)
+    }
+
+    /// Converts SDL's normalized touch coordinates to logical pixels.
+    fn touch_position(&self, x: f32, y: f32, scale_factor: f64) -> iced_core::Point {
+        let s = 1.0 / scale_factor as f32;
+        iced_core::Point::new(x * self.size.width * s, y * self.size.height * s)
+    }
+
+    /// Translates an SDL event, where `scale_factor` is the number of SDL window units per
+    /// logical pixel. Each event comes with what iced can't tell about it.
+    pub fn window_event(
+        &mut self,
+        event: &Event,
+        scale_factor: f64,
+    ) -> Vec<(iced_core::Event, EventInfo)> {
+        use iced_core::touch;
+        let mut events = Vec::new();
+        // SDL follows a key press with the text it produced, if any, so the press is held back
+        // until we know whether there is text to go with it
+        let mut key = None;
+        if !matches!(event, Event::TextInput { .. }) {
+            key = self.pending_key.take();
+        }
+        match event {
+            Event::Window {
+                win_event: WindowEvent::SizeChanged(width, height),
+                ..
+            } => {
+                self.size = iced_core::Size::new(*width as f32, *height as f32);
+            }
+            // Moving to another display can change the scale, so have the layout redone
+            Event::Window {
+                win_event: WindowEvent::DisplayChanged(_),
+                ..
+            } => {
+                events.push(iced_core::Event::Window(iced_core::window::Event::Resized(
+                    self.size * (1.0 / scale_factor as f32),
+                )));
+            }
+            // SDL only tells us about drops, so files are hovered and dropped at once and there
+            // is never a hover left to cancel
+            Event::DropFile { filename, .. } => {
+                let path = std::path::PathBuf::from(filename);
+                events.push(iced_core::Event::Window(
+                    iced_core::window::Event::FileHovered(path.clone()),
+                ));
+                events.push(iced_core::Event::Window(
+                    iced_core::window::Event::FileDropped(path),
+                ));
+            }
+            Event::TextEditing { text, .. } => {
+                self.preedit = (!text.is_empty()).then(|| text.clone());
+            }
+            Event::TextInput { .. } => {
+                self.preedit = None;
+            }
+            Event::FingerDown {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                ..
+            } => {
+                let id = self.finger(*touch_id, *finger_id);
+                let position = self.touch_position(*x, *y, scale_factor);
+                events.push(iced_core::Event::Touch(touch::Event::FingerPressed {
+                    id,
+                    position,
+                }));
+            }
+            Event::FingerMotion {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                ..
+            } => {
+                let id = self.finger(*touch_id, *finger_id);
+                let position = self.touch_position(*x, *y, scale_factor);
+                events.push(iced_core::Event::Touch(touch::Event::FingerMoved {
+                    id,
+                    position,
+                }));
+            }
+            Event::FingerUp {
+                touch_id,
+                finger_id,
+                x,
+                y,
+                ..
+            } => {
+                let id = self.finger(*touch_id, *finger_id);
+                self.fingers.remove(&(*touch_id, *finger_id));
+                let position = self.touch_position(*x, *y, scale_factor);
+                events.push(iced_core::Event::Touch(touch::Event::FingerLifted {
+                    id,
+                    position,
+                }));
+            }
+            _ => (),
+        }
+        if let Event::KeyDown { keymod, .. } | Event::KeyUp { keymod, .. } = event {
+            let modifiers = modifier(keymod);
+            if modifiers != self.modifiers {
+                self.modifiers = modifiers;
+                events.push(iced_core::Event::Keyboard(
+                    iced_core::keyboard::Event::ModifiersChanged(modifiers),
+                ));
+            }
+        }
+        match (event, window_event(event, self.modifiers, scale_factor)) {
+            (
+                Event::KeyDown {
+                    repeat, keycode, ..
+                },
+                evt,
+            ) => {
+                let info = EventInfo {
+                    repeat: *repeat,
+                    keycode: *keycode,
+                };
+                self.pending_key = evt.map(|evt| (evt, info));
+            }
+            (Event::TextInput { text, .. }, evt) => match self.pending_key.take() {
+                Some((evt, info)) => key = Some((key_text(evt, text), info)),
+                // Text without a key press, e.g., from the input method
+                None => events.extend(evt),
+            },
+            (_, evt) => events.extend(evt),
+        }
+        key.into_iter()
+            .chain(events.into_iter().map(|evt| (evt, EventInfo::default())))
+            .collect()
+    }
+}
+
+/// Attaches the text produced by a key press to it. SDL repeats the text along with the press,
+/// which is what text inputs need.
+fn key_text(event: iced_core::Event, text: &str) -> iced_core::Event {
+    use iced_core::keyboard::{self, Key};
+    match event {
+        iced_core::Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            physical_key,
+            location,
+            modifiers,
+            ..
+        }) => {
+            let text = iced_core::SmolStr::new(text);
+            // The key stays unmodified, while the text has shift and friends applied
+            let modified_key = match key {
+                Key::Character(_) => Key::Character(text.clone()),
+                _ => key.clone(),
+            };
+            iced_core::Event::Keyboard(keyboard::Event::KeyPressed {
+                key,
+                modified_key,
+                physical_key,
+                location,
+                modifiers,
+                text: Some(text),
+            })
+        }
+        e => e,
+    }
+}
+
+pub fn window_event(
+    event: &Event,
+    modifiers: iced_core::keyboard::Modifiers,
+    scale_factor: f64,
+) -> Option<iced_core::Event> {
     match event {
         Event::Window {
             //window_id,
             win_event: WindowEvent::SizeChanged(width, height),
             ..
-        } => Some(iced_core::Event::Window(iced_core::window::Event::Resized(
-            iced::Size {
-                width: *width as f32,
-                height: *height as f32,
-            },
-        ))),
+        } => {
+            let s = 1.0 / scale_factor as f32;
+            Some(iced_core::Event::Window(iced_core::window::Event::Resized(
+                iced::Size {
+                    width: *width as f32 * s,
+                    height: *height as f32 * s,
+                },
+            )))
+        }
+        Event::Window {
+            win_event: WindowEvent::Moved(x, y),
+            ..
+        } => {
+            let s = 1.0 / scale_factor as f32;
+            Some(iced_core::Event::Window(iced_core::window::Event::Moved(
+                iced_core::Point::new(*x as f32 * s, *y as f32 * s),
+            )))
+        }
+        Event::Window {
+            win_event:
+                WindowEvent::Shown
+                | WindowEvent::Exposed
+                | WindowEvent::Maximized
+                | WindowEvent::Restored,
+            ..
+        } => Some(iced_core::Event::Window(
+            iced_core::window::Event::RedrawRequested(std::time::Instant::now()),
+        )),
+        Event::Window {
+            win_event: WindowEvent::Close,
+            ..
+        } => Some(iced_core::Event::Window(
+            iced_core::window::Event::CloseRequested,
+        )),
         Event::Window {
             win_event: WindowEvent::Enter,
             ..
@@ -186,6 +1039,16 @@ pub fn window_event(event: &Event, scale_factor: f64) -> Option<iced_core::Event
                 iced_core::mouse::Event::ButtonReleased(btn),
             ))
         }
+        Event::MouseWheel {
+            direction,
+            precise_x,
+            precise_y,
+            ..
+        } => Some(iced_core::Event::Mouse(
+            iced_core::mouse::Event::WheelScrolled {
+                delta: scroll_delta(*precise_x, *precise_y, direction),
+            },
+        )),
         Event::TextInput { text, .. } => Some(iced_core::Event::Keyboard(
             iced_core::keyboard::Event::KeyPressed {
                 key: iced::keyboard::Key::Unidentified,
@@ -194,19 +1057,19 @@ pub fn window_event(event: &Event, scale_factor: f64) -> Option<iced_core::Event
                     iced_core::keyboard::key::NativeCode::Unidentified,
                 ),
                 location: iced_core::keyboard::Location::Standard,
-                modifiers: iced_core::keyboard::Modifiers::empty(),
+                modifiers,
                 text: Some(iced_core::SmolStr::new(text)),
             },
         )),
         Event::KeyDown {
-            keycode,
-            scancode,
-            keymod,
-            ..
+            keycode, scancode, ..
         } => {
-            let k = match keycode {
-                Some(c) => key(c),
-                None => iced::keyboard::Key::Unidentified,
+            let (k, loc) = match keycode {
+                Some(c) => (key(c), location(c)),
+                None => (
+                    iced::keyboard::Key::Unidentified,
+                    iced_core::keyboard::Location::Standard,
+                ),
             };
             Some(iced_core::Event::Keyboard(
                 iced_core::keyboard::Event::KeyPressed {
@@ -218,21 +1081,21 @@ pub fn window_event(event: &Event, scale_factor: f64) -> Option<iced_core::Event
                             iced_core::keyboard::key::NativeCode::Unidentified,
                         ),
                     },
-                    location: iced_core::keyboard::Location::Standard,
-                    modifiers: modifier(keymod),
+                    location: loc,
+                    modifiers,
                     text: None,
                 },
             ))
         }
         Event::KeyUp {
-            keycode,
-            scancode,
-            keymod,
-            ..
+            keycode, scancode, ..
         } => {
-            let k = match keycode {
-                Some(c) => key(c),
-                None => iced::keyboard::Key::Unidentified,
+            let (k, loc) = match keycode {
+                Some(c) => (key(c), location(c)),
+                None => (
+                    iced::keyboard::Key::Unidentified,
+                    iced_core::keyboard::Location::Standard,
+                ),
             };
             Some(iced_core::Event::Keyboard(
                 iced_core::keyboard::Event::KeyReleased {
@@ -244,8 +1107,8 @@ pub fn window_event(event: &Event, scale_factor: f64) -> Option<iced_core::Event
                             iced_core::keyboard::key::NativeCode::Unidentified,
                         ),
                     },
-                    location: iced_core::keyboard::Location::Standard,
-                    modifiers: modifier(keymod),
+                    location: loc,
+                    modifiers,
                 },
             ))
         }
@@ -255,3 +1118,235 @@ pub fn window_event(event: &Event, scale_factor: f64) -> Option<iced_core::Event
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_core::keyboard::key::{Code, Key, Named, Physical};
+    use iced_core::keyboard::{self, Location, Modifiers};
+    use iced_core::SmolStr;
+    use sdl2::keyboard::{Keycode, Mod, Scancode};
+
+    fn key_down(keycode: Keycode, scancode: Scancode, keymod: Mod, repeat: bool) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 1,
+            keycode: Some(keycode),
+            scancode: Some(scancode),
+            keymod,
+            repeat,
+        }
+    }
+
+    fn key_up(keycode: Keycode, scancode: Scancode, keymod: Mod) -> Event {
+        Event::KeyUp {
+            timestamp: 0,
+            window_id: 1,
+            keycode: Some(keycode),
+            scancode: Some(scancode),
+            keymod,
+            repeat: false,
+        }
+    }
+
+    fn text_input(text: &str) -> Event {
+        Event::TextInput {
+            timestamp: 0,
+            window_id: 1,
+            text: text.to_string(),
+        }
+    }
+
+    fn pressed(key: Key, modified_key: Key, code: Code, modifiers: Modifiers) -> iced_core::Event {
+        let text = match &modified_key {
+            Key::Character(c) => Some(c.clone()),
+            _ => None,
+        };
+        iced_core::Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            modified_key,
+            physical_key: Physical::Code(code),
+            location: Location::Standard,
+            modifiers,
+            text,
+        })
+    }
+
+    fn key_info(keycode: Keycode, repeat: bool) -> EventInfo {
+        EventInfo {
+            repeat,
+            keycode: Some(keycode),
+        }
+    }
+
+    fn modifiers_changed(modifiers: Modifiers) -> iced_core::Event {
+        iced_core::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers))
+    }
+
+    #[test]
+    fn physical_keys() {
+        assert_eq!(physical_key(&Scancode::A), Physical::Code(Code::KeyA));
+        assert_eq!(native_scancode(&physical_key(&Scancode::A)), None);
+        // Keys without a code still differ from each other
+        for scancode in [
+            Scancode::Stop,
+            Scancode::Execute,
+            Scancode::KpTab,
+            Scancode::Lang6,
+        ] {
+            let physical = physical_key(&scancode);
+            assert!(matches!(physical, Physical::Unidentified(_)));
+            assert_eq!(native_scancode(&physical), Some(scancode));
+        }
+    }
+
+    #[test]
+    fn key_with_text() {
+        let mut adapter = Adapter::new(800, 600);
+        let events = adapter.window_event(
+            &key_down(Keycode::A, Scancode::A, Mod::LSHIFTMOD, false),
+            1.0,
+        );
+        assert_eq!(
+            events,
+            vec![(modifiers_changed(Modifiers::SHIFT), EventInfo::default())]
+        );
+        let events = adapter.window_event(&text_input("A"), 1.0);
+        let a = Key::Character(SmolStr::new("a"));
+        let shifted = Key::Character(SmolStr::new("A"));
+        let expected = pressed(a, shifted, Code::KeyA, Modifiers::SHIFT);
+        assert_eq!(events, vec![(expected, key_info(Keycode::A, false))]);
+        assert_eq!(adapter.flush(), None);
+    }
+
+    #[test]
+    fn key_without_text() {
+        let mut adapter = Adapter::new(800, 600);
+        let left = pressed(
+            Key::Named(Named::ArrowLeft),
+            Key::Named(Named::ArrowLeft),
+            Code::ArrowLeft,
+            Modifiers::empty(),
+        );
+        assert!(adapter
+            .window_event(
+                &key_down(Keycode::LEFT, Scancode::Left, Mod::NOMOD, false),
+                1.0
+            )
+            .is_empty());
+        // Held back until the next event shows there is no text
+        let focus_lost = Event::Window {
+            timestamp: 0,
+            window_id: 1,
+            win_event: WindowEvent::FocusLost,
+        };
+        let events = adapter.window_event(&focus_lost, 1.0);
+        let unfocused = iced_core::Event::Window(iced_core::window::Event::Unfocused);
+        assert_eq!(
+            events,
+            vec![
+                (left.clone(), key_info(Keycode::LEFT, false)),
+                (unfocused, EventInfo::default()),
+            ]
+        );
+        // or until the frame ends
+        assert!(adapter
+            .window_event(
+                &key_down(Keycode::LEFT, Scancode::Left, Mod::NOMOD, false),
+                1.0
+            )
+            .is_empty());
+        assert_eq!(
+            adapter.flush(),
+            Some((left, key_info(Keycode::LEFT, false)))
+        );
+        assert_eq!(adapter.flush(), None);
+    }
+
+    #[test]
+    fn key_repeat() {
+        let mut adapter = Adapter::new(800, 600);
+        adapter.window_event(&key_down(Keycode::A, Scancode::A, Mod::NOMOD, true), 1.0);
+        let events = adapter.window_event(&text_input("a"), 1.0);
+        let a = Key::Character(SmolStr::new("a"));
+        let expected = pressed(a.clone(), a, Code::KeyA, Modifiers::empty());
+        assert_eq!(events, vec![(expected, key_info(Keycode::A, true))]);
+        adapter.window_event(
+            &key_down(Keycode::LEFT, Scancode::Left, Mod::NOMOD, true),
+            1.0,
+        );
+        assert_eq!(
+            adapter.flush().map(|(_, info)| info),
+            Some(key_info(Keycode::LEFT, true))
+        );
+    }
+
+    #[test]
+    fn text_without_key() {
+        let mut adapter = Adapter::new(800, 600);
+        let events = adapter.window_event(&text_input("日本"), 1.0);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            (
+                iced_core::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: Key::Unidentified,
+                    text: Some(text),
+                    ..
+                }),
+                info,
+            ) => {
+                assert_eq!(text.as_str(), "日本");
+                assert_eq!(*info, EventInfo::default());
+            }
+            e => panic!("unexpected event {:?}", e),
+        }
+        assert_eq!(adapter.flush(), None);
+    }
+
+    #[test]
+    fn modifiers_only_on_change() {
+        let mut adapter = Adapter::new(800, 600);
+        let events = adapter.window_event(
+            &key_down(Keycode::RSHIFT, Scancode::RShift, Mod::RSHIFTMOD, false),
+            1.0,
+        );
+        assert_eq!(
+            events,
+            vec![(modifiers_changed(Modifiers::SHIFT), EventInfo::default())]
+        );
+        let events = adapter.window_event(
+            &key_down(Keycode::A, Scancode::A, Mod::RSHIFTMOD | Mod::NUMMOD, false),
+            1.0,
+        );
+        // Only the shift press that was held back, and no new modifiers
+        assert_eq!(events.len(), 1);
+        assert!(!events.iter().any(|(e, _)| matches!(
+            e,
+            iced_core::Event::Keyboard(keyboard::Event::ModifiersChanged(_))
+        )));
+        adapter.flush();
+        let events = adapter.window_event(
+            &key_down(
+                Keycode::LCTRL,
+                Scancode::LCtrl,
+                Mod::RSHIFTMOD | Mod::LCTRLMOD,
+                false,
+            ),
+            1.0,
+        );
+        assert_eq!(
+            events,
+            vec![(
+                modifiers_changed(Modifiers::SHIFT | Modifiers::CTRL),
+                EventInfo::default(),
+            )]
+        );
+        adapter.flush();
+        let events =
+            adapter.window_event(&key_up(Keycode::LCTRL, Scancode::LCtrl, Mod::NOMOD), 1.0);
+        assert_eq!(
+            events[0],
+            (modifiers_changed(Modifiers::empty()), EventInfo::default())
+        );
+    }
+}
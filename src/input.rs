@@ -0,0 +1,320 @@
+use sdl2::controller::Button;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use std::path::{Path, PathBuf};
+
+/// Things the player can do that aren't handled by the toolkit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Menu,
+    Quit,
+    Screenshot,
+    Console,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [
+        Action::Menu,
+        Action::Quit,
+        Action::Screenshot,
+        Action::Console,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Menu => "menu",
+            Action::Quit => "quit",
+            Action::Screenshot => "screenshot",
+            Action::Console => "console",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(Keycode),
+    Mouse(MouseButton),
+    Controller(Button),
+}
+
+fn mouse_button_name(button: &MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+        MouseButton::X1 => "x1",
+        MouseButton::X2 => "x2",
+        MouseButton::Unknown => "unknown",
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Binding::Key(k) => write!(f, "key:{}", k.name()),
+            Binding::Mouse(b) => write!(f, "mouse:{}", mouse_button_name(b)),
+            Binding::Controller(b) => write!(f, "controller:{}", b.string()),
+        }
+    }
+}
+
+impl std::str::FromStr for Binding {
+    type Err = String;
+
+    /// Parses bindings such as `key:Escape`, `mouse:right` or `controller:start`.
+    fn from_str(s: &str) -> Result<Binding, String> {
+        let invalid = || format!("Invalid binding '{}'", s);
+        let (device, name) = s.split_once(':').ok_or_else(invalid)?;
+        match device {
+            "key" => Keycode::from_name(name).map(Binding::Key),
+            "mouse" => [
+                MouseButton::Left,
+                MouseButton::Middle,
+                MouseButton::Right,
+                MouseButton::X1,
+                MouseButton::X2,
+            ]
+            .into_iter()
+            .find(|b| mouse_button_name(b) == name)
+            .map(Binding::Mouse),
+            "controller" => Button::from_string(name).map(Binding::Controller),
+            _ => None,
+        }
+        .ok_or_else(invalid)
+    }
+}
+
+/// Where the bindings are kept, next to the config file in use.
+pub fn path(config: &crate::config::Config) -> Option<PathBuf> {
+    config
+        .path
+        .as_ref()
+        .map(|path| path.with_file_name("bindings.conf"))
+}
+
+/// Maps keys, mouse buttons and controller buttons to actions. An action can have any number of
+/// bindings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    bindings: Vec<(Action, Binding)>,
+}
+
+/// The bindings in use, shared with Lua.
+pub static BINDINGS: std::sync::Mutex<Bindings> = std::sync::Mutex::new(Bindings::empty());
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings::new()
+    }
+}
+
+impl std::fmt::Display for Bindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (action, binding) in &self.bindings {
+            writeln!(f, "{} = {}", action.name(), binding)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Bindings {
+    type Err = String;
+
+    /// Parses one `action = binding` per line. Lines starting with `#` are comments. Errors
+    /// start with the line number.
+    fn from_str(s: &str) -> Result<Bindings, String> {
+        let mut bindings = Vec::new();
+        for (n, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let binding = line
+                .split_once('=')
+                .ok_or_else(|| String::from("expected 'action = binding'"))
+                .and_then(|(action, binding)| {
+                    let action = Action::from_name(action.trim())
+                        .ok_or_else(|| format!("Unknown action '{}'", action.trim()))?;
+                    Ok((action, binding.trim().parse()?))
+                })
+                .map_err(|e| format!("{}: {}", n + 1, e))?;
+            bindings.push(binding);
+        }
+        Ok(Bindings { bindings })
+    }
+}
+
+impl Bindings {
+    pub const fn empty() -> Bindings {
+        Bindings {
+            bindings: Vec::new(),
+        }
+    }
+
+    pub fn new() -> Bindings {
+        Bindings {
+            bindings: vec![
+                (Action::Menu, Binding::Controller(Button::Start)),
+                (Action::Quit, Binding::Key(Keycode::Escape)),
+                (Action::Screenshot, Binding::Key(Keycode::F12)),
+                (Action::Console, Binding::Key(Keycode::F2)),
+            ],
+        }
+    }
+
+    /// Loads bindings written by [`Bindings::save`].
+    pub fn load(path: &Path) -> Result<Bindings, String> {
+        let data =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        data.parse()
+            .map_err(|e| format!("{}:{}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        std::fs::write(path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn bindings(&self, action: Action) -> Vec<Binding> {
+        self.bindings
+            .iter()
+            .filter(|(a, _)| *a == action)
+            .map(|(_, b)| *b)
+            .collect()
+    }
+
+    /// Adds a binding to an action, taking it away from any other action that had it.
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        self.bindings.retain(|(_, b)| *b != binding);
+        self.bindings.push((action, binding));
+    }
+
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.retain(|(a, _)| *a != action);
+    }
+
+    fn find(&self, pred: impl Fn(&Binding) -> bool) -> Option<Action> {
+        self.bindings.iter().find(|(_, b)| pred(b)).map(|(a, _)| *a)
+    }
+
+    /// Action for input the toolkit let through. Keys are matched by their SDL keycode, so that
+    /// bindings follow the keyboard layout.
+    pub fn game_event(
+        &self,
+        event: &iced_core::Event,
+        info: crate::iced_sdl::EventInfo,
+    ) -> Option<Action> {
+        match (event, info.keycode) {
+            (
+                iced_core::Event::Keyboard(iced_core::keyboard::Event::KeyPressed { .. }),
+                Some(keycode),
+            ) => self.find(|b| *b == Binding::Key(keycode)),
+            (iced_core::Event::Mouse(iced_core::mouse::Event::ButtonPressed(button)), _) => self
+                .find(|b| match b {
+                    Binding::Mouse(m) => crate::iced_sdl::mouse_button(m) == *button,
+                    _ => false,
+                }),
+            _ => None,
+        }
+    }
+
+    /// Action for a controller button. Controllers aren't seen by the toolkit, so the caller has
+    /// to make sure it isn't using them for navigation.
+    pub fn controller_event(&self, event: &Event) -> Option<Action> {
+        match event {
+            Event::ControllerButtonDown { button, .. } => self.find(|b| match b {
+                Binding::Controller(c) => c == button,
+                _ => false,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut bindings = Bindings::new();
+        bindings.bind(Action::Console, Binding::Mouse(MouseButton::X1));
+        bindings.bind(Action::Menu, Binding::Controller(Button::DPadLeft));
+        assert_eq!(bindings.to_string().parse(), Ok(bindings));
+        assert_eq!("".parse(), Ok(Bindings::empty()));
+    }
+
+    #[test]
+    fn parse() {
+        let bindings: Bindings = "# Comment\n\n  quit =  key:Escape \nmenu=controller:start\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            bindings.bindings(Action::Quit),
+            [Binding::Key(Keycode::Escape)]
+        );
+        assert_eq!(
+            bindings.bindings(Action::Menu),
+            [Binding::Controller(Button::Start)]
+        );
+        assert_eq!(
+            "quit = key:Escape\nfly = key:Space".parse::<Bindings>(),
+            Err(String::from("2: Unknown action 'fly'"))
+        );
+        assert_eq!(
+            "quit key:Escape".parse::<Bindings>(),
+            Err(String::from("1: expected 'action = binding'"))
+        );
+        assert_eq!(
+            "quit = joystick:1".parse::<Bindings>(),
+            Err(String::from("1: Invalid binding 'joystick:1'"))
+        );
+    }
+
+    #[test]
+    fn bind() {
+        let mut bindings = Bindings::new();
+        bindings.bind(Action::Menu, Binding::Key(Keycode::Escape));
+        assert!(bindings.bindings(Action::Quit).is_empty());
+        assert!(bindings
+            .bindings(Action::Menu)
+            .contains(&Binding::Key(Keycode::Escape)));
+        bindings.unbind(Action::Menu);
+        assert!(bindings.bindings(Action::Menu).is_empty());
+    }
+
+    #[test]
+    fn game_event() {
+        use crate::iced_sdl::EventInfo;
+        let mut bindings = Bindings::new();
+        bindings.bind(Action::Console, Binding::Key(Keycode::KP_1));
+        // What iced says about the key doesn't matter, only the keycode does
+        let press = iced_core::Event::Keyboard(iced_core::keyboard::Event::KeyPressed {
+            key: iced_core::keyboard::Key::Unidentified,
+            modified_key: iced_core::keyboard::Key::Unidentified,
+            physical_key: iced_core::keyboard::key::Physical::Unidentified(
+                iced_core::keyboard::key::NativeCode::Unidentified,
+            ),
+            location: iced_core::keyboard::Location::Standard,
+            modifiers: iced_core::keyboard::Modifiers::empty(),
+            text: None,
+        });
+        let info = |keycode| EventInfo {
+            repeat: false,
+            keycode,
+        };
+        assert_eq!(
+            bindings.game_event(&press, info(Some(Keycode::KP_1))),
+            Some(Action::Console)
+        );
+        assert_eq!(bindings.game_event(&press, info(Some(Keycode::Num1))), None);
+        assert_eq!(bindings.game_event(&press, info(None)), None);
+    }
+}
@@ -0,0 +1,29 @@
+//pub mod controls;
+pub mod config;
+mod focus;
+pub mod gamepad;
+pub mod host;
+pub mod iced_sdl;
+pub mod input;
+pub mod offscreen;
+pub mod os_window;
+pub mod pointer;
+mod program;
+pub mod replay;
+pub mod timing;
+pub mod toolkit;
+
+use std::sync::atomic::AtomicBool;
+
+static QUIT: AtomicBool = AtomicBool::new(false);
+
+pub fn quit() {
+    QUIT.store(true, std::sync::atomic::Ordering::Relaxed)
+}
+
+static FLIGHT: AtomicBool = AtomicBool::new(false);
+
+/// Flight mode steers with the mouse, so it gets captured whenever the toolkit isn't open.
+pub fn set_flight_mode(flight: bool) {
+    FLIGHT.store(flight, std::sync::atomic::Ordering::Relaxed)
+}
@@ -1,111 +1,157 @@
-//mod controls;
-mod iced_sdl;
+mod golden;
 mod menu_main;
 mod nlua;
 mod scene;
-mod toolkit;
 mod toolkit_lua;
 
+use integration::config::Config;
+use integration::host::{Game, Gpu, SdlIcedHost};
+use integration::input::{self, Action};
+use integration::toolkit;
+use menu_main::MenuMain;
 use nlua::NLua;
 use scene::Scene;
 
 use iced_wgpu::wgpu;
 
-use sdl2::event::{Event, WindowEvent};
-use sdl2::keyboard::Keycode;
+struct Naev<'a> {
+    scene: Scene<'a>,
+    lua: &'a mlua::Lua,
+    /// Lua coroutine to resume when a toolkit window closes.
+    lua_thread: Option<mlua::Thread>,
+}
+
+impl Game for Naev<'_> {
+    fn fixed_update(&mut self, _host: &mut SdlIcedHost, dt: f64) {
+        self.scene.update(dt as f32);
+    }
+
+    fn render(&self, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) -> bool {
+        // We clear the frame
+        let mut render_pass = self.scene.clear(view, encoder, iced_core::Color::BLACK);
 
-use std::sync::atomic::AtomicBool;
-static QUIT: AtomicBool = AtomicBool::new(false);
+        // Draw the scene
+        self.scene.draw(&mut render_pass);
+        true
+    }
+
+    fn action(&mut self, action: Action, toolkit_open: bool) {
+        match action {
+            Action::Menu if toolkit_open => (),
+            Action::Menu => toolkit::MESSAGE_QUEUE
+                .lock()
+                .unwrap()
+                .push(toolkit::Message::open(MenuMain::new)),
+            Action::Quit => integration::quit(),
+            Action::Screenshot | Action::Console => {
+                println!("'{}' is not implemented yet!", action.name())
+            }
+        }
+    }
 
-pub fn quit() {
-    QUIT.store( true, std::sync::atomic::Ordering::Relaxed)
+    /// Steering is up to Lua, which gets the motion if it defines `motion( dx, dy )`.
+    fn motion(&mut self, dx: i32, dy: i32) {
+        let motion: Option<mlua::Function> = match self.lua.globals().get("motion") {
+            Ok(motion) => motion,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        if let Some(motion) = motion {
+            if let Err(e) = motion.call::<()>((dx, dy)) {
+                println!("{}", e);
+            }
+        }
+    }
+
+    // TODO check if window was closed and another was opened
+    fn window_closed(&mut self) {
+        if let Some(th) = &self.lua_thread {
+            th.resume::<()>(()).unwrap();
+            if th.status() != mlua::ThreadStatus::Resumable {
+                self.lua_thread = None;
+            }
+        }
+    }
 }
 
 pub fn main() -> Result<(), String> {
     // Show logs from wgpu
     env_logger::init();
 
+    let mut args = std::env::args().skip(1).peekable();
+    if args.next_if_eq("golden").is_some() {
+        return golden::main(args);
+    }
+
+    let config = match Config::load(args) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if config.help {
+        println!("{}", integration::config::USAGE);
+        return Ok(());
+    }
+
+    if config.list_adapters {
+        Gpu::list_adapters();
+        return Ok(());
+    }
+
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
     let window = video_subsystem
-        .window("Raw Window Handle Example", 800, 600)
+        .window(&config.title, config.width, config.height)
         .position_centered()
         .resizable()
         .allow_highdpi()
         .build()
         .map_err(|e| e.to_string())?;
-    let (width, height) = window.size();
-
-    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-        backends: wgpu::Backends::PRIMARY, //backends: wgpu::Backends::GL,
-        ..Default::default()
-    });
-    let surface = unsafe {
-        match instance
-            .create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(&window).unwrap())
-        {
-            Ok(s) => s,
-            Err(e) => return Err(e.to_string()),
-        }
-    };
-    let adapter_opt = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::HighPerformance,
-        force_fallback_adapter: false,
-        compatible_surface: Some(&surface),
-    }));
-    let adapter = match adapter_opt {
-        Some(a) => a,
-        None => return Err(String::from("No adapter found")),
-    };
 
-    let (device, queue) = match pollster::block_on(adapter.request_device(
-        &wgpu::DeviceDescriptor {
-            required_limits: wgpu::Limits::default(),
-            label: Some("device"),
-            required_features: wgpu::Features::empty(),
-            memory_hints: Default::default(),
-        },
-        None,
-    )) {
-        Ok(a) => a,
-        Err(e) => return Err(e.to_string()),
+    let (gpu, surface) = Gpu::new(&window, &config)?;
+    let mut host = SdlIcedHost::new(&sdl_context, &gpu, window, surface, &config)?;
+
+    // Load the input bindings, falling back to the defaults
+    let bindings_path = input::path(&config);
+    let bindings = match &bindings_path {
+        Some(path) if path.exists() => input::Bindings::load(path).unwrap_or_else(|e| {
+            println!("Failed to load bindings: {}", e);
+            input::Bindings::new()
+        }),
+        _ => input::Bindings::new(),
     };
-
-    let format = wgpu::TextureFormat::Bgra8UnormSrgb;
-    let mut config = wgpu::SurfaceConfiguration {
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format,
-        width,
-        height,
-        present_mode: wgpu::PresentMode::Fifo,
-        alpha_mode: wgpu::CompositeAlphaMode::Auto,
-        view_formats: Vec::default(),
-        desired_maximum_frame_latency: 2,
-    };
-    surface.configure(&device, &config);
-
-    let scale_factor = 1.2; // TODO hook with SDL or something
-    let mut scene = Scene::new(&device, &queue, format);
-    let mut engine = iced_wgpu::Engine::new(&adapter, &device, &queue, format, None);
-    let mut clipboard = iced_sdl::Clipboard::new(video_subsystem.clipboard());
-    let mut toolkit =
-        toolkit::Toolkit::new(&mut engine, &device, &queue, scale_factor, width, height);
-
-    /*
-    program.open( toolkit_lua::ToolkitWindow::Lua( toolkit_lua::ToolkitWindowLua::new().unwrap_or_else(|err| {
-        panic!("{}", err);
-    })));
-    */
+    *input::BINDINGS.lock().unwrap() = bindings.clone();
 
     // Create the Lua environment
     let nlua = NLua::new();
     let lua = nlua.lua;
     toolkit_lua::open_iced(&lua).unwrap();
-    lua.load(include_str!("main.lua")).exec().unwrap();
-    let mut nlua_cur_th: Option<mlua::Thread> = None;
+    match &config.lua {
+        Some(path) => {
+            let source =
+                std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            lua.load(source)
+                .set_name(format!("@{}", path.display()))
+                .exec()
+                .map_err(|e| e.to_string())?;
+        }
+        None => lua.load(include_str!("main.lua")).exec().unwrap(),
+    }
+    host.queue_message(toolkit::Message::open(MenuMain::new));
+
+    let mut naev = Naev {
+        scene: Scene::new(&gpu.device, &gpu.queue, gpu.format),
+        lua: &lua,
+        lua_thread: None,
+    };
     //let f: mlua::Function = lua.globals().get("main").unwrap();
     /*
-    let mut nlua_cur_th: Option<mlua::Thread> = {
+    naev.lua_thread = {
         let th: mlua::Thread = lua.load("coroutine.create( main )").eval().unwrap();
         th.resume::<()>(()).unwrap_or_else(|err| panic!("{}", err));
         match th.status() {
@@ -114,92 +160,13 @@ pub fn main() -> Result<(), String> {
         }
     };
     */
+    host.run(&mut naev)?;
 
-    //program.open(toolkit::ToolkitWindow::MenuMain(menu_main::MenuMain::new()));
-    toolkit.queue_message(toolkit::Message::OpenMenuMain);
-
-    let mut event_pump = sdl_context.event_pump()?;
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match &event {
-                Event::Window {
-                    window_id,
-                    win_event: WindowEvent::SizeChanged(width, height),
-                    ..
-                } if *window_id == window.id() => {
-                    config.width = *width as u32;
-                    config.height = *height as u32;
-                    surface.configure(&device, &config);
-                }
-                Event::MouseMotion { x, y, .. }
-                | Event::MouseButtonDown { x, y, .. }
-                | Event::MouseButtonUp { x, y, .. } => {
-                    toolkit.update_cursor_position(*x as f32, *y as f32);
-                }
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => {
-                    break 'running;
-                }
-                _e => {
-                    //dbg!(e);
-                }
-            }
-
-            //Map window event to iced event
-            if let Some(evt) = iced_sdl::window_event(&event, scale_factor) {
-                toolkit.queue_event(evt);
-            }
-        }
-        toolkit.update(&mut clipboard, &mut nlua_cur_th);
-        if QUIT.load( std::sync::atomic::Ordering::Relaxed) {
-            break 'running;
-        }
-
-        let frame = match surface.get_current_texture() {
-            Ok(frame) => frame,
-            Err(err) => {
-                let reason = match err {
-                    wgpu::SurfaceError::Timeout => "Timeout",
-                    wgpu::SurfaceError::Outdated => "Outdated",
-                    wgpu::SurfaceError::Lost => "Lost",
-                    wgpu::SurfaceError::OutOfMemory => "OutOfMemory",
-                };
-                println!("Failed to get current surface texture! Reason: {}", reason);
-                if let wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost = err {
-                    let (w, h) = window.size();
-                    config.width = w;
-                    config.height = h;
-                    surface.configure(&device, &config);
-                }
-                continue 'running;
-            }
-        };
-
-        let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("command_encoder"),
-        });
-
-        {
-            // We clear the frame
-            let mut render_pass = scene.clear(&view, &mut encoder, iced_core::Color::BLACK);
-
-            // Draw the scene
-            scene.draw(&mut render_pass);
-        }
-        toolkit.draw(&mut engine, &view, &mut encoder, &frame);
-        engine.submit(&queue, encoder);
-        frame.present();
-
-        scene.update(0.01);
-
-        if QUIT.load( std::sync::atomic::Ordering::Relaxed) {
-            break 'running;
+    // Only write the bindings if Lua changed them
+    let changed = input::BINDINGS.lock().unwrap().clone();
+    if let Some(path) = bindings_path.filter(|_| changed != bindings) {
+        if let Err(e) = changed.save(&path) {
+            println!("Failed to save bindings: {}", e);
         }
     }
 
@@ -1,11 +1,11 @@
-use crate::toolkit;
-use crate::toolkit::Message as MessageBase;
 use iced::{Center, Fill};
 use iced_core::{Element, Theme};
 use iced_wgpu::Renderer;
 use iced_widget::{button, column, container};
+use integration::toolkit;
+use integration::toolkit::Message as MessageBase;
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Default)]
 pub struct MenuMain {}
 
 #[derive(Debug, Clone)]
@@ -37,16 +37,28 @@ fn pilot_new(accept: bool, name: String) -> MessageBase {
 
 impl toolkit::Window for MenuMain {
     fn update(&mut self, message: MessageBase) -> MessageBase {
-        if let MessageBase::MenuMain(m) = message {
+        if let Some(m) = message.downcast::<Message>() {
             match m {
                 Message::NewGame => MessageBase::OpenDialogueInput(
                     String::from("What will you name your new pilot?"),
                     &pilot_new,
                 ),
                 Message::ExitGame => {
-                    crate::quit();
+                    integration::quit();
                     MessageBase::CloseWindow
                 },
+                Message::Editors => {
+                    integration::os_window::open(
+                        "Editors",
+                        400,
+                        300,
+                        MessageBase::OpenDialogueOK(
+                            String::from("Not implemented yet!"),
+                            &toolkit::dialogue_noop_ok,
+                        ),
+                    );
+                    MessageBase::None
+                }
                 Message::Options => MessageBase::OpenDialogueOK(
                     String::from("Not implemented yet!"),
                     &toolkit::dialogue_noop_ok,
@@ -58,22 +70,27 @@ impl toolkit::Window for MenuMain {
         }
     }
 
+    fn cancel(&self) -> MessageBase {
+        // Nowhere to go back to from the main menu
+        MessageBase::None
+    }
+
     fn view(&self) -> Element<MessageBase, Theme, Renderer> {
         container(
             container(
                 column![
                     button("Load Game"),
-                    button("New Game").on_press(MessageBase::MenuMain(Message::NewGame)),
-                    button("Editors").on_press(MessageBase::MenuMain(Message::Editors)),
-                    button("Options").on_press(MessageBase::MenuMain(Message::Options)),
+                    button("New Game").on_press(MessageBase::custom(Message::NewGame)),
+                    button("Editors").on_press(MessageBase::custom(Message::Editors)),
+                    button("Options").on_press(MessageBase::custom(Message::Options)),
                     //button("Credits").on_press(Message::Credits),
-                    button("Exit Game").on_press(MessageBase::MenuMain(Message::ExitGame)),
+                    button("Exit Game").on_press(MessageBase::custom(Message::ExitGame)),
                 ]
                 .spacing(10)
                 .padding(20)
                 .align_x(Center),
             )
-            .style(crate::toolkit::window)
+            .style(toolkit::window)
             .align_x(Center)
             .width(150),
         )
@@ -1,3 +1,5 @@
+use integration::input;
+
 pub struct NLua {
     pub lua: mlua::Lua,
 }
@@ -8,15 +10,93 @@ fn open_naev(lua: &mlua::Lua) -> mlua::Result<()> {
     naev_table.set(
         "quit",
         lua.create_function(|_lua, ()| -> mlua::Result<()> {
-            crate::quit();
+            integration::quit();
+            Ok(())
+        })?,
+    )?;
+    naev_table.set(
+        "set_flight_mode",
+        lua.create_function(|_lua, flight: bool| -> mlua::Result<()> {
+            integration::set_flight_mode(flight);
             Ok(())
         })?,
     )?;
+    naev_table.set(
+        "timing",
+        lua.create_function(|lua, ()| -> mlua::Result<mlua::Table> {
+            let timing = *integration::timing::TIMING.lock().unwrap();
+            let t = lua.create_table()?;
+            t.set("dt", timing.dt)?;
+            t.set("elapsed", timing.elapsed)?;
+            t.set("alpha", timing.alpha)?;
+            t.set("frame", timing.frame)?;
+            Ok(t)
+        })?,
+    )?;
+    naev_table.set("input", open_input(lua)?)?;
     globals.set("naev", naev_table)?;
 
     Ok(())
 }
 
+fn action(name: &str) -> mlua::Result<input::Action> {
+    input::Action::from_name(name)
+        .ok_or_else(|| mlua::Error::RuntimeError(format!("Unknown action '{}'", name)))
+}
+
+/// Lets Lua look at and change the input bindings.
+fn open_input(lua: &mlua::Lua) -> mlua::Result<mlua::Table> {
+    let input_table = lua.create_table()?;
+    input_table.set(
+        "actions",
+        lua.create_function(|_lua, ()| -> mlua::Result<Vec<String>> {
+            Ok(input::Action::ALL
+                .iter()
+                .map(|a| String::from(a.name()))
+                .collect())
+        })?,
+    )?;
+    input_table.set(
+        "bindings",
+        lua.create_function(|_lua, name: String| -> mlua::Result<Vec<String>> {
+            let action = action(&name)?;
+            Ok(input::BINDINGS
+                .lock()
+                .unwrap()
+                .bindings(action)
+                .iter()
+                .map(|b| b.to_string())
+                .collect())
+        })?,
+    )?;
+    input_table.set(
+        "bind",
+        lua.create_function(
+            |_lua, (name, binding): (String, String)| -> mlua::Result<()> {
+                let action = action(&name)?;
+                let binding = binding.parse().map_err(mlua::Error::RuntimeError)?;
+                input::BINDINGS.lock().unwrap().bind(action, binding);
+                Ok(())
+            },
+        )?,
+    )?;
+    input_table.set(
+        "unbind",
+        lua.create_function(|_lua, name: String| -> mlua::Result<()> {
+            let action = action(&name)?;
+            input::BINDINGS.lock().unwrap().unbind(action);
+            Ok(())
+        })?,
+    )?;
+    Ok(input_table)
+}
+
+impl Default for NLua {
+    fn default() -> NLua {
+        NLua::new()
+    }
+}
+
 impl NLua {
     pub fn new() -> NLua {
         let lua = mlua::Lua::new();
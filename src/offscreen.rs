@@ -0,0 +1,151 @@
+use crate::host::{draw_frame, Game, Gpu};
+use crate::iced_sdl;
+use crate::toolkit::Toolkit;
+use iced_wgpu::wgpu;
+
+/// Renders the game and the toolkit to a texture instead of a window, so that frames can be
+/// saved and compared without a display.
+pub struct Offscreen<'a> {
+    gpu: &'a Gpu,
+    engine: iced_wgpu::Engine,
+    texture: wgpu::Texture,
+    clipboard: iced_sdl::Clipboard,
+    pub toolkit: Toolkit<'a>,
+}
+
+impl<'a> Offscreen<'a> {
+    pub fn new(gpu: &'a Gpu, width: u32, height: u32, scale_factor: f64) -> Offscreen<'a> {
+        let mut engine =
+            iced_wgpu::Engine::new(&gpu.adapter, &gpu.device, &gpu.queue, gpu.format, None);
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: gpu.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let toolkit = Toolkit::new(
+            &mut engine,
+            &gpu.device,
+            &gpu.queue,
+            scale_factor,
+            width,
+            height,
+        );
+        Offscreen {
+            gpu,
+            engine,
+            texture,
+            clipboard: iced_sdl::Clipboard::memory(),
+            toolkit,
+        }
+    }
+
+    /// Lets the toolkit handle whatever messages were queued, such as opening windows.
+    pub fn update(&mut self) {
+        self.toolkit.update(&mut self.clipboard);
+    }
+
+    /// Draws a frame with [`draw_frame`] and reads it back as tightly packed RGBA.
+    pub fn render(&mut self, game: Option<&dyn Game>) -> Vec<u8> {
+        let device = &self.gpu.device;
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("command_encoder"),
+        });
+        draw_frame(
+            &mut self.toolkit,
+            &mut self.engine,
+            game,
+            &view,
+            &mut encoder,
+            self.gpu.format,
+        );
+        self.engine.submit(&self.gpu.queue, encoder);
+
+        // Rows have to be copied out padded to the alignment wgpu wants
+        let size = self.texture.size();
+        let row = size.width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = row.div_ceil(align) * align;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen_readback"),
+            size: (padded_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("readback_encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        self.gpu.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| ());
+        device.poll(wgpu::Maintain::Wait);
+        let data = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((row * size.height) as usize);
+        for r in data.chunks(padded_row as usize) {
+            pixels.extend_from_slice(&r[..row as usize]);
+        }
+        drop(data);
+        buffer.unmap();
+        pixels
+    }
+}
+
+/// Writes tightly packed RGBA pixels as a PNG.
+pub fn save_png(
+    path: &std::path::Path,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Reads a PNG as tightly packed RGBA pixels, along with its size.
+pub fn load_png(path: &std::path::Path) -> Result<(u32, u32, Vec<u8>), String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut pixels)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("{}: not an 8-bit RGBA image", path.display()));
+    }
+    pixels.truncate(info.buffer_size());
+    Ok((info.width, info.height, pixels))
+}
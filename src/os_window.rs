@@ -0,0 +1,271 @@
+use crate::host::{draw_frame, Game, Gpu};
+use crate::iced_sdl;
+use crate::toolkit::{self, Toolkit};
+use iced_wgpu::wgpu;
+use sdl2::event::{Event, WindowEvent};
+
+/// A new OS window to be opened by the main loop, showing whatever `message` opens.
+pub struct Request {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub message: toolkit::Message,
+}
+
+pub static REQUEST_QUEUE: std::sync::Mutex<Vec<Request>> = std::sync::Mutex::new(Vec::new());
+
+/// Opens a separate OS window for a toolkit window, such as an editor. The OS window is closed
+/// along with the last toolkit window in it.
+pub fn open(title: &str, width: u32, height: u32, message: toolkit::Message) {
+    REQUEST_QUEUE.lock().unwrap().push(Request {
+        title: String::from(title),
+        width,
+        height,
+        message,
+    });
+}
+
+pub fn create_surface(
+    instance: &wgpu::Instance,
+    window: &sdl2::video::Window,
+) -> Result<wgpu::Surface<'static>, String> {
+    unsafe {
+        let target = wgpu::SurfaceTargetUnsafe::from_window(window).map_err(|e| e.to_string())?;
+        instance
+            .create_surface_unsafe(target)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Works out how many physical pixels there are per SDL window unit, and how many physical
+/// pixels to use per logical toolkit pixel. When the OS already scales windows for us (macOS,
+/// Wayland) the drawable is larger than the window and we follow that, otherwise we go by the DPI
+/// of the display the window is on.
+fn scale_factor(window: &sdl2::video::Window, forced: Option<f64>) -> (f64, f64) {
+    let (w, _) = window.size();
+    let (dw, _) = window.drawable_size();
+    let density = match w {
+        0 => 1.0,
+        w => dw as f64 / w as f64,
+    };
+    if let Some(scale_factor) = forced {
+        return (density, scale_factor);
+    }
+    if density > 1.0 {
+        return (density, density);
+    }
+    let dpi_scale = window
+        .display_index()
+        .and_then(|i| window.subsystem().display_dpi(i))
+        .map(|(ddpi, _, _)| (ddpi as f64 / 96.0 * 4.0).round() / 4.0)
+        .unwrap_or(1.0);
+    (density, dpi_scale.max(1.0))
+}
+
+pub struct OsWindow<'a> {
+    // The surface has to be dropped before the window it was created from
+    surface: wgpu::Surface<'static>,
+    window: sdl2::video::Window,
+    config: wgpu::SurfaceConfiguration,
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    adapter: iced_sdl::Adapter,
+    pub toolkit: Toolkit<'a>,
+    // Physical pixels per window unit, and per logical pixel
+    density: f64,
+    scale_factor: f64,
+    forced_scale_factor: Option<f64>,
+    minimized: bool,
+}
+
+impl<'a> OsWindow<'a> {
+    pub fn new(
+        window: sdl2::video::Window,
+        surface: wgpu::Surface<'static>,
+        gpu: &'a Gpu,
+        engine: &mut iced_wgpu::Engine,
+        forced_scale_factor: Option<f64>,
+    ) -> OsWindow<'a> {
+        let (device, queue) = (&gpu.device, &gpu.queue);
+        let (density, scale_factor) = scale_factor(&window, forced_scale_factor);
+        let (window_width, window_height) = window.size();
+        let (width, height) = window.drawable_size();
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: gpu.format,
+            width,
+            height,
+            present_mode: gpu.present_mode,
+            alpha_mode: gpu.alpha_mode,
+            view_formats: Vec::default(),
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(device, &config);
+        let toolkit = Toolkit::new(engine, device, queue, scale_factor, width, height);
+        OsWindow {
+            surface,
+            window,
+            config,
+            device,
+            queue,
+            adapter: iced_sdl::Adapter::new(window_width, window_height),
+            toolkit,
+            density,
+            scale_factor,
+            forced_scale_factor,
+            minimized: false,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.window.id()
+    }
+
+    pub fn window_mut(&mut self) -> &mut sdl2::video::Window {
+        &mut self.window
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::Window { win_event, .. } => match win_event {
+                WindowEvent::SizeChanged(..) => {
+                    self.resize();
+                    self.update_scale_factor();
+                }
+                WindowEvent::DisplayChanged(_) => self.update_scale_factor(),
+                WindowEvent::Minimized | WindowEvent::Hidden => self.minimized = true,
+                WindowEvent::Restored | WindowEvent::Maximized | WindowEvent::Shown => {
+                    self.minimized = false
+                }
+                _ => (),
+            },
+            Event::MouseMotion { x, y, .. }
+            | Event::MouseButtonDown { x, y, .. }
+            | Event::MouseButtonUp { x, y, .. } => {
+                let d = self.density as f32;
+                self.toolkit
+                    .update_cursor_position(*x as f32 * d, *y as f32 * d);
+            }
+            Event::DropFile { filename, .. } => {
+                self.toolkit
+                    .queue_message(toolkit::Message::Dropped(toolkit::Dropped::File(
+                        filename.into(),
+                    )));
+            }
+            Event::DropText { filename, .. } => {
+                self.toolkit
+                    .queue_message(toolkit::Message::Dropped(toolkit::Dropped::Text(
+                        filename.clone(),
+                    )));
+            }
+            _ => (),
+        }
+
+        //Map window event to iced event, which is in window units rather than pixels
+        let scale = self.scale_factor / self.density;
+        for (evt, info) in self.adapter.window_event(event, scale) {
+            match evt {
+                // Only the toolkit needs to know the window is showing again
+                iced_core::Event::Window(iced_core::window::Event::RedrawRequested(now)) => {
+                    self.toolkit.redraw(now)
+                }
+                evt => self.toolkit.queue_event(evt, info),
+            }
+        }
+    }
+
+    /// Updates the toolkit, returning the events it handled, whether it captured them and what
+    /// iced can't tell about them.
+    pub fn update(
+        &mut self,
+        clipboard: &mut impl iced_core::Clipboard,
+    ) -> Vec<(
+        iced_core::Event,
+        iced_core::event::Status,
+        iced_sdl::EventInfo,
+    )> {
+        if let Some((evt, info)) = self.adapter.flush() {
+            self.toolkit.queue_event(evt, info);
+        }
+        self.toolkit.set_preedit(self.adapter.preedit());
+        self.toolkit.update(clipboard)
+    }
+
+    /// Sizes the surface and the toolkit to the drawable area of the window.
+    fn resize(&mut self) {
+        let (width, height) = self.window.drawable_size();
+        // Surfaces can't be empty, which happens while minimized on some platforms
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(self.device, &self.config);
+        self.toolkit.resize(width, height);
+    }
+
+    /// Follows the window to displays with a different DPI, or changes in how the OS scales it.
+    fn update_scale_factor(&mut self) {
+        let (density, scale_factor) = scale_factor(&self.window, self.forced_scale_factor);
+        self.density = density;
+        if scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            self.toolkit.set_scale_factor(scale_factor);
+        }
+    }
+
+    /// Area of the focused text input in window units, for the input method.
+    pub fn text_input_rect(&self) -> Option<iced_core::Rectangle> {
+        self.toolkit
+            .text_input_rect()
+            .map(|bounds| bounds * (1.0 / self.density as f32))
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Draws a frame with [`draw_frame`] and shows it.
+    pub fn render(&mut self, engine: &mut iced_wgpu::Engine, game: Option<&dyn Game>) {
+        if self.minimized {
+            return;
+        }
+
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(err) => {
+                let reason = match err {
+                    wgpu::SurfaceError::Timeout => "Timeout",
+                    wgpu::SurfaceError::Outdated => "Outdated",
+                    wgpu::SurfaceError::Lost => "Lost",
+                    wgpu::SurfaceError::OutOfMemory => "OutOfMemory",
+                };
+                println!("Failed to get current surface texture! Reason: {}", reason);
+                if let wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost = err {
+                    self.resize();
+                }
+                return;
+            }
+        };
+
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("command_encoder"),
+            });
+
+        draw_frame(
+            &mut self.toolkit,
+            engine,
+            game,
+            &view,
+            &mut encoder,
+            frame.texture.format(),
+        );
+        engine.submit(self.queue, encoder);
+        frame.present();
+    }
+}
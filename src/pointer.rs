@@ -0,0 +1,51 @@
+use sdl2::event::Event;
+use sdl2::mouse::MouseUtil;
+
+/// Switches the mouse between relative mode, for when the game is steering with it, and the
+/// normal pointer the toolkit needs. The toolkit always wins while it has a window open.
+pub struct Pointer {
+    mouse: MouseUtil,
+    relative: bool,
+}
+
+impl Pointer {
+    pub fn new(mouse: MouseUtil) -> Pointer {
+        Pointer {
+            mouse,
+            relative: false,
+        }
+    }
+
+    /// Grabs the window and goes into relative mode if the game wants it and the toolkit is
+    /// closed, and releases the pointer otherwise.
+    pub fn update(
+        &mut self,
+        window: &mut sdl2::video::Window,
+        game_wants: bool,
+        toolkit_open: bool,
+    ) {
+        let relative = game_wants && !toolkit_open;
+        if relative == self.relative {
+            return;
+        }
+        self.relative = relative;
+        self.mouse.set_relative_mouse_mode(relative);
+        window.set_mouse_grab(relative);
+        self.mouse.show_cursor(!relative);
+        if !relative {
+            // Where the cursor was hidden is meaningless, so bring it back in the middle
+            let (w, h) = window.size();
+            self.mouse
+                .warp_mouse_in_window(window, w as i32 / 2, h as i32 / 2);
+        }
+    }
+
+    /// In relative mode, mouse motion only makes sense as a relative offset for the game, as the
+    /// absolute position doesn't move. Returns the offset for such events.
+    pub fn relative_motion(&self, event: &Event) -> Option<(i32, i32)> {
+        match event {
+            Event::MouseMotion { xrel, yrel, .. } if self.relative => Some((*xrel, *yrel)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use iced_core::event;
+use iced_core::widget::operation::{self, Operation};
+use iced_core::{mouse, renderer, Clipboard, Event, Size};
+use iced_runtime::user_interface::{self, UserInterface};
+use iced_runtime::{Debug, Program, Task};
+
+/// iced's `program::State`, except that updating tells how each queued event was handled. iced
+/// only hands back the events nobody captured, which can't be matched up with the queued ones
+/// when several of them are the same.
+pub struct State<P: Program + 'static> {
+    program: P,
+    cache: Option<user_interface::Cache>,
+    queued_events: Vec<Event>,
+    queued_messages: Vec<P::Message>,
+    mouse_interaction: mouse::Interaction,
+}
+
+impl<P: Program + 'static> State<P> {
+    pub fn new(
+        mut program: P,
+        bounds: Size,
+        renderer: &mut P::Renderer,
+        debug: &mut Debug,
+    ) -> Self {
+        let user_interface = build_user_interface(
+            &mut program,
+            user_interface::Cache::default(),
+            renderer,
+            bounds,
+            debug,
+        );
+        let cache = Some(user_interface.into_cache());
+        State {
+            program,
+            cache,
+            queued_events: Vec::new(),
+            queued_messages: Vec::new(),
+            mouse_interaction: mouse::Interaction::None,
+        }
+    }
+
+    pub fn program(&self) -> &P {
+        &self.program
+    }
+
+    pub fn queue_event(&mut self, event: Event) {
+        self.queued_events.push(event);
+    }
+
+    pub fn queue_message(&mut self, message: P::Message) {
+        self.queued_messages.push(message);
+    }
+
+    pub fn mouse_interaction(&self) -> mouse::Interaction {
+        self.mouse_interaction
+    }
+
+    /// Processes the queued events and messages, and draws the widgets again. Returns how each
+    /// event was handled, in the order they were queued, and the tasks the messages produced.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        bounds: Size,
+        cursor: mouse::Cursor,
+        renderer: &mut P::Renderer,
+        theme: &P::Theme,
+        style: &renderer::Style,
+        clipboard: &mut dyn Clipboard,
+        debug: &mut Debug,
+    ) -> (Vec<event::Status>, Option<Task<P::Message>>) {
+        let mut user_interface = build_user_interface(
+            &mut self.program,
+            self.cache.take().unwrap(),
+            renderer,
+            bounds,
+            debug,
+        );
+
+        debug.event_processing_started();
+        let mut messages = Vec::new();
+        let (_, statuses) = user_interface.update(
+            &self.queued_events,
+            cursor,
+            renderer,
+            clipboard,
+            &mut messages,
+        );
+        self.queued_events.clear();
+        messages.append(&mut self.queued_messages);
+        debug.event_processing_finished();
+
+        if messages.is_empty() {
+            debug.draw_started();
+            self.mouse_interaction = user_interface.draw(renderer, theme, style, cursor);
+            debug.draw_finished();
+            self.cache = Some(user_interface.into_cache());
+            return (statuses, None);
+        }
+
+        // The program changes with the messages, so the widgets have to be built again
+        let cache = user_interface.into_cache();
+        let tasks = Task::batch(messages.into_iter().map(|message| {
+            debug.log_message(&message);
+            debug.update_started();
+            let task = self.program.update(message);
+            debug.update_finished();
+            task
+        }));
+
+        let mut user_interface =
+            build_user_interface(&mut self.program, cache, renderer, bounds, debug);
+        debug.draw_started();
+        self.mouse_interaction = user_interface.draw(renderer, theme, style, cursor);
+        debug.draw_finished();
+        self.cache = Some(user_interface.into_cache());
+
+        (statuses, Some(tasks))
+    }
+
+    pub fn operate(
+        &mut self,
+        renderer: &mut P::Renderer,
+        operations: impl Iterator<Item = Box<dyn Operation>>,
+        bounds: Size,
+        debug: &mut Debug,
+    ) {
+        let mut user_interface = build_user_interface(
+            &mut self.program,
+            self.cache.take().unwrap(),
+            renderer,
+            bounds,
+            debug,
+        );
+
+        for operation in operations {
+            let mut current = Some(operation);
+            while let Some(mut operation) = current.take() {
+                user_interface.operate(renderer, operation.as_mut());
+                if let operation::Outcome::Chain(next) = operation.finish() {
+                    current = Some(next);
+                }
+            }
+        }
+
+        self.cache = Some(user_interface.into_cache());
+    }
+}
+
+fn build_user_interface<'a, P: Program>(
+    program: &'a mut P,
+    cache: user_interface::Cache,
+    renderer: &mut P::Renderer,
+    size: Size,
+    debug: &mut Debug,
+) -> UserInterface<'a, P::Message, P::Theme, P::Renderer> {
+    debug.view_started();
+    let view = program.view();
+    debug.view_finished();
+
+    debug.layout_started();
+    let user_interface = UserInterface::build(view, size, cache, renderer);
+    debug.layout_finished();
+
+    user_interface
+}
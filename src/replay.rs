@@ -0,0 +1,816 @@
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+use std::collections::VecDeque;
+use std::io::Write;
+
+// Recordings are plain text with one event per line:
+//
+//    <frame> <timestamp> <kind> <fields>...
+//
+// Every frame also gets a `<frame> dt <seconds>` line with its length, so that replays step the
+// simulation the same way.
+//
+// Strings are escaped so that they never contain spaces or newlines. Events the game doesn't
+// care about (raw joysticks, audio devices, user events, ...) are not recorded.
+
+/// Writes the SDL event stream to a file, frame by frame.
+pub struct Recorder {
+    file: std::io::BufWriter<std::fs::File>,
+    frame: u64,
+}
+
+impl Recorder {
+    pub fn new(path: &str) -> Result<Recorder, String> {
+        let file = std::fs::File::create(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Recorder {
+            file: std::io::BufWriter::new(file),
+            frame: 0,
+        })
+    }
+
+    /// Records the length of a frame and all the events handled in it, and moves on to the
+    /// next one.
+    pub fn record_frame(&mut self, dt: f64, events: &[Event]) {
+        if let Err(e) = writeln!(self.file, "{} dt {}", self.frame, dt) {
+            println!("Failed to record frame: {}", e);
+        }
+        for event in events {
+            if let Some(line) = encode(event) {
+                if let Err(e) = writeln!(self.file, "{} {}", self.frame, line) {
+                    println!("Failed to record event: {}", e);
+                }
+            }
+        }
+        self.frame += 1;
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.file.flush() {
+            println!("Failed to save recording: {}", e);
+        }
+    }
+}
+
+/// Feeds a recorded event stream back, giving each frame the events it originally had.
+pub struct Replay {
+    events: VecDeque<(u64, Event)>,
+    dts: VecDeque<(u64, f64)>,
+    frame: u64,
+}
+
+impl Replay {
+    pub fn new(path: &str) -> Result<Replay, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut events = VecDeque::new();
+        let mut dts = VecDeque::new();
+        for (n, line) in data.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let (frame, rest) = line
+                .split_once(' ')
+                .ok_or_else(|| String::from("missing event"))
+                .and_then(|(frame, rest)| {
+                    let frame: u64 = frame.parse().map_err(|_| String::from("invalid frame"))?;
+                    Ok((frame, rest))
+                })
+                .map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+            match rest.strip_prefix("dt ") {
+                Some(dt) => dts.push_back((
+                    frame,
+                    dt.parse()
+                        .map_err(|_| format!("{}:{}: invalid frame time", path, n + 1))?,
+                )),
+                None => events.push_back((
+                    frame,
+                    decode(rest).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?,
+                )),
+            }
+        }
+        Ok(Replay {
+            events,
+            dts,
+            frame: 0,
+        })
+    }
+
+    /// Returns the length of the next frame, if it was recorded, and its events.
+    pub fn next_frame(&mut self) -> (Option<f64>, Vec<Event>) {
+        let mut dt = None;
+        while let Some((frame, _)) = self.dts.front() {
+            if *frame > self.frame {
+                break;
+            }
+            dt = Some(self.dts.pop_front().unwrap().1);
+        }
+        let mut events = Vec::new();
+        while let Some((frame, _)) = self.events.front() {
+            if *frame > self.frame {
+                break;
+            }
+            events.push(self.events.pop_front().unwrap().1);
+        }
+        self.frame += 1;
+        (dt, events)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty() && self.dts.is_empty()
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ' ' => out.push_str("\\s"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            _ => return Err(format!("invalid escape in '{}'", s)),
+        }
+    }
+    Ok(out)
+}
+
+fn window_event_name(e: &WindowEvent) -> (&'static str, i32, i32) {
+    match *e {
+        WindowEvent::None => ("None", 0, 0),
+        WindowEvent::Shown => ("Shown", 0, 0),
+        WindowEvent::Hidden => ("Hidden", 0, 0),
+        WindowEvent::Exposed => ("Exposed", 0, 0),
+        WindowEvent::Moved(x, y) => ("Moved", x, y),
+        WindowEvent::Resized(w, h) => ("Resized", w, h),
+        WindowEvent::SizeChanged(w, h) => ("SizeChanged", w, h),
+        WindowEvent::Minimized => ("Minimized", 0, 0),
+        WindowEvent::Maximized => ("Maximized", 0, 0),
+        WindowEvent::Restored => ("Restored", 0, 0),
+        WindowEvent::Enter => ("Enter", 0, 0),
+        WindowEvent::Leave => ("Leave", 0, 0),
+        WindowEvent::FocusGained => ("FocusGained", 0, 0),
+        WindowEvent::FocusLost => ("FocusLost", 0, 0),
+        WindowEvent::Close => ("Close", 0, 0),
+        WindowEvent::TakeFocus => ("TakeFocus", 0, 0),
+        WindowEvent::HitTest => ("HitTest", 0, 0),
+        WindowEvent::ICCProfChanged => ("ICCProfChanged", 0, 0),
+        WindowEvent::DisplayChanged(d) => ("DisplayChanged", d, 0),
+    }
+}
+
+fn window_event_from_name(name: &str, a: i32, b: i32) -> Option<WindowEvent> {
+    Some(match name {
+        "None" => WindowEvent::None,
+        "Shown" => WindowEvent::Shown,
+        "Hidden" => WindowEvent::Hidden,
+        "Exposed" => WindowEvent::Exposed,
+        "Moved" => WindowEvent::Moved(a, b),
+        "Resized" => WindowEvent::Resized(a, b),
+        "SizeChanged" => WindowEvent::SizeChanged(a, b),
+        "Minimized" => WindowEvent::Minimized,
+        "Maximized" => WindowEvent::Maximized,
+        "Restored" => WindowEvent::Restored,
+        "Enter" => WindowEvent::Enter,
+        "Leave" => WindowEvent::Leave,
+        "FocusGained" => WindowEvent::FocusGained,
+        "FocusLost" => WindowEvent::FocusLost,
+        "Close" => WindowEvent::Close,
+        "TakeFocus" => WindowEvent::TakeFocus,
+        "HitTest" => WindowEvent::HitTest,
+        "ICCProfChanged" => WindowEvent::ICCProfChanged,
+        "DisplayChanged" => WindowEvent::DisplayChanged(a),
+        _ => return None,
+    })
+}
+
+fn opt<T: std::fmt::Display>(v: Option<T>) -> String {
+    match v {
+        Some(v) => v.to_string(),
+        None => String::from("-"),
+    }
+}
+
+fn encode(event: &Event) -> Option<String> {
+    let ts = event.get_timestamp();
+    Some(match event {
+        Event::Quit { .. } => format!("{} Quit", ts),
+        Event::Window {
+            window_id,
+            win_event,
+            ..
+        } => {
+            let (name, a, b) = window_event_name(win_event);
+            format!("{} Window {} {} {} {}", ts, window_id, name, a, b)
+        }
+        Event::KeyDown {
+            window_id,
+            keycode,
+            scancode,
+            keymod,
+            repeat,
+            ..
+        }
+        | Event::KeyUp {
+            window_id,
+            keycode,
+            scancode,
+            keymod,
+            repeat,
+            ..
+        } => format!(
+            "{} {} {} {} {} {} {}",
+            ts,
+            if let Event::KeyDown { .. } = event {
+                "KeyDown"
+            } else {
+                "KeyUp"
+            },
+            window_id,
+            opt(keycode.map(|k| k.into_i32())),
+            opt(scancode.map(|s| s as i32)),
+            keymod.bits(),
+            repeat
+        ),
+        Event::TextEditing {
+            window_id,
+            text,
+            start,
+            length,
+            ..
+        } => format!(
+            "{} TextEditing {} {} {} {}",
+            ts,
+            window_id,
+            start,
+            length,
+            escape(text)
+        ),
+        Event::TextInput {
+            window_id, text, ..
+        } => format!("{} TextInput {} {}", ts, window_id, escape(text)),
+        Event::MouseMotion {
+            window_id,
+            which,
+            mousestate,
+            x,
+            y,
+            xrel,
+            yrel,
+            ..
+        } => format!(
+            "{} MouseMotion {} {} {} {} {} {} {}",
+            ts,
+            window_id,
+            which,
+            mousestate.to_sdl_state(),
+            x,
+            y,
+            xrel,
+            yrel
+        ),
+        Event::MouseButtonDown {
+            window_id,
+            which,
+            mouse_btn,
+            clicks,
+            x,
+            y,
+            ..
+        }
+        | Event::MouseButtonUp {
+            window_id,
+            which,
+            mouse_btn,
+            clicks,
+            x,
+            y,
+            ..
+        } => format!(
+            "{} {} {} {} {} {} {} {}",
+            ts,
+            if let Event::MouseButtonDown { .. } = event {
+                "MouseButtonDown"
+            } else {
+                "MouseButtonUp"
+            },
+            window_id,
+            which,
+            *mouse_btn as u8,
+            clicks,
+            x,
+            y
+        ),
+        Event::MouseWheel {
+            window_id,
+            which,
+            x,
+            y,
+            direction,
+            precise_x,
+            precise_y,
+            mouse_x,
+            mouse_y,
+            ..
+        } => format!(
+            "{} MouseWheel {} {} {} {} {} {} {} {} {}",
+            ts,
+            window_id,
+            which,
+            x,
+            y,
+            direction.to_ll(),
+            precise_x,
+            precise_y,
+            mouse_x,
+            mouse_y
+        ),
+        Event::ControllerAxisMotion {
+            which, axis, value, ..
+        } => format!(
+            "{} ControllerAxisMotion {} {} {}",
+            ts,
+            which,
+            axis.string(),
+            value
+        ),
+        Event::ControllerButtonDown { which, button, .. } => {
+            format!("{} ControllerButtonDown {} {}", ts, which, button.string())
+        }
+        Event::ControllerButtonUp { which, button, .. } => {
+            format!("{} ControllerButtonUp {} {}", ts, which, button.string())
+        }
+        Event::ControllerDeviceAdded { which, .. } => {
+            format!("{} ControllerDeviceAdded {}", ts, which)
+        }
+        Event::ControllerDeviceRemoved { which, .. } => {
+            format!("{} ControllerDeviceRemoved {}", ts, which)
+        }
+        Event::FingerDown {
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure,
+            ..
+        }
+        | Event::FingerUp {
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure,
+            ..
+        }
+        | Event::FingerMotion {
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure,
+            ..
+        } => format!(
+            "{} {} {} {} {} {} {} {} {}",
+            ts,
+            match event {
+                Event::FingerDown { .. } => "FingerDown",
+                Event::FingerUp { .. } => "FingerUp",
+                _ => "FingerMotion",
+            },
+            touch_id,
+            finger_id,
+            x,
+            y,
+            dx,
+            dy,
+            pressure
+        ),
+        Event::DropBegin { window_id, .. } => format!("{} DropBegin {}", ts, window_id),
+        Event::DropComplete { window_id, .. } => format!("{} DropComplete {}", ts, window_id),
+        Event::DropFile {
+            window_id,
+            filename,
+            ..
+        } => format!("{} DropFile {} {}", ts, window_id, escape(filename)),
+        Event::DropText {
+            window_id,
+            filename,
+            ..
+        } => format!("{} DropText {} {}", ts, window_id, escape(filename)),
+        _ => return None,
+    })
+}
+
+fn decode(line: &str) -> Result<Event, String> {
+    let fields: Vec<&str> = line.split(' ').collect();
+    fn arg<T: std::str::FromStr>(fields: &[&str], i: usize) -> Result<T, String> {
+        let f = fields
+            .get(i)
+            .ok_or_else(|| format!("missing field {}", i))?;
+        f.parse()
+            .map_err(|_| format!("invalid field {} '{}'", i, f))
+    }
+    fn opt_arg<T: std::str::FromStr>(fields: &[&str], i: usize) -> Result<Option<T>, String> {
+        match fields.get(i) {
+            Some(&"-") => Ok(None),
+            _ => Ok(Some(arg(fields, i)?)),
+        }
+    }
+    fn text_arg(fields: &[&str], i: usize) -> Result<String, String> {
+        unescape(
+            fields
+                .get(i)
+                .ok_or_else(|| format!("missing field {}", i))?,
+        )
+    }
+
+    let timestamp: u32 = arg(&fields, 0)?;
+    let kind: &str = fields.get(1).ok_or_else(|| String::from("missing kind"))?;
+    Ok(match kind {
+        "Quit" => Event::Quit { timestamp },
+        "Window" => {
+            let name: String = arg(&fields, 3)?;
+            Event::Window {
+                timestamp,
+                window_id: arg(&fields, 2)?,
+                win_event: window_event_from_name(&name, arg(&fields, 4)?, arg(&fields, 5)?)
+                    .ok_or_else(|| format!("unknown window event '{}'", name))?,
+            }
+        }
+        "KeyDown" | "KeyUp" => {
+            let window_id = arg(&fields, 2)?;
+            let keycode = opt_arg::<i32>(&fields, 3)?.and_then(Keycode::from_i32);
+            let scancode = opt_arg::<i32>(&fields, 4)?.and_then(Scancode::from_i32);
+            let keymod = Mod::from_bits_truncate(arg(&fields, 5)?);
+            let repeat = arg(&fields, 6)?;
+            if kind == "KeyDown" {
+                Event::KeyDown {
+                    timestamp,
+                    window_id,
+                    keycode,
+                    scancode,
+                    keymod,
+                    repeat,
+                }
+            } else {
+                Event::KeyUp {
+                    timestamp,
+                    window_id,
+                    keycode,
+                    scancode,
+                    keymod,
+                    repeat,
+                }
+            }
+        }
+        "TextEditing" => Event::TextEditing {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+            start: arg(&fields, 3)?,
+            length: arg(&fields, 4)?,
+            text: text_arg(&fields, 5)?,
+        },
+        "TextInput" => Event::TextInput {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+            text: text_arg(&fields, 3)?,
+        },
+        "MouseMotion" => Event::MouseMotion {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+            which: arg(&fields, 3)?,
+            mousestate: MouseState::from_sdl_state(arg(&fields, 4)?),
+            x: arg(&fields, 5)?,
+            y: arg(&fields, 6)?,
+            xrel: arg(&fields, 7)?,
+            yrel: arg(&fields, 8)?,
+        },
+        "MouseButtonDown" | "MouseButtonUp" => {
+            let window_id = arg(&fields, 2)?;
+            let which = arg(&fields, 3)?;
+            let mouse_btn = MouseButton::from_ll(arg(&fields, 4)?);
+            let clicks = arg(&fields, 5)?;
+            let x = arg(&fields, 6)?;
+            let y = arg(&fields, 7)?;
+            if kind == "MouseButtonDown" {
+                Event::MouseButtonDown {
+                    timestamp,
+                    window_id,
+                    which,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            } else {
+                Event::MouseButtonUp {
+                    timestamp,
+                    window_id,
+                    which,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            }
+        }
+        "MouseWheel" => Event::MouseWheel {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+            which: arg(&fields, 3)?,
+            x: arg(&fields, 4)?,
+            y: arg(&fields, 5)?,
+            direction: MouseWheelDirection::from_ll(arg(&fields, 6)?),
+            precise_x: arg(&fields, 7)?,
+            precise_y: arg(&fields, 8)?,
+            mouse_x: arg(&fields, 9)?,
+            mouse_y: arg(&fields, 10)?,
+        },
+        "ControllerAxisMotion" => {
+            let name: String = arg(&fields, 3)?;
+            Event::ControllerAxisMotion {
+                timestamp,
+                which: arg(&fields, 2)?,
+                axis: Axis::from_string(&name).ok_or_else(|| format!("unknown axis '{}'", name))?,
+                value: arg(&fields, 4)?,
+            }
+        }
+        "ControllerButtonDown" | "ControllerButtonUp" => {
+            let which = arg(&fields, 2)?;
+            let name: String = arg(&fields, 3)?;
+            let button =
+                Button::from_string(&name).ok_or_else(|| format!("unknown button '{}'", name))?;
+            if kind == "ControllerButtonDown" {
+                Event::ControllerButtonDown {
+                    timestamp,
+                    which,
+                    button,
+                }
+            } else {
+                Event::ControllerButtonUp {
+                    timestamp,
+                    which,
+                    button,
+                }
+            }
+        }
+        "ControllerDeviceAdded" => Event::ControllerDeviceAdded {
+            timestamp,
+            which: arg(&fields, 2)?,
+        },
+        "ControllerDeviceRemoved" => Event::ControllerDeviceRemoved {
+            timestamp,
+            which: arg(&fields, 2)?,
+        },
+        "FingerDown" | "FingerUp" | "FingerMotion" => {
+            let touch_id = arg(&fields, 2)?;
+            let finger_id = arg(&fields, 3)?;
+            let x = arg(&fields, 4)?;
+            let y = arg(&fields, 5)?;
+            let dx = arg(&fields, 6)?;
+            let dy = arg(&fields, 7)?;
+            let pressure = arg(&fields, 8)?;
+            match kind {
+                "FingerDown" => Event::FingerDown {
+                    timestamp,
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    pressure,
+                },
+                "FingerUp" => Event::FingerUp {
+                    timestamp,
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    pressure,
+                },
+                _ => Event::FingerMotion {
+                    timestamp,
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    pressure,
+                },
+            }
+        }
+        "DropBegin" => Event::DropBegin {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+        },
+        "DropComplete" => Event::DropComplete {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+        },
+        "DropFile" => Event::DropFile {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+            filename: text_arg(&fields, 3)?,
+        },
+        "DropText" => Event::DropText {
+            timestamp,
+            window_id: arg(&fields, 2)?,
+            filename: text_arg(&fields, 3)?,
+        },
+        _ => return Err(format!("unknown event '{}'", kind)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let events = [
+            Event::Quit { timestamp: 1 },
+            Event::Window {
+                timestamp: 2,
+                window_id: 1,
+                win_event: WindowEvent::SizeChanged(800, 600),
+            },
+            Event::KeyDown {
+                timestamp: 3,
+                window_id: 1,
+                keycode: Some(Keycode::A),
+                scancode: Some(Scancode::A),
+                keymod: Mod::LSHIFTMOD | Mod::NUMMOD,
+                repeat: true,
+            },
+            Event::KeyUp {
+                timestamp: 4,
+                window_id: 1,
+                keycode: None,
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            },
+            Event::TextInput {
+                timestamp: 5,
+                window_id: 1,
+                text: String::from("a b\\c"),
+            },
+            Event::TextEditing {
+                timestamp: 6,
+                window_id: 1,
+                text: String::new(),
+                start: 0,
+                length: 0,
+            },
+            Event::MouseButtonDown {
+                timestamp: 7,
+                window_id: 1,
+                which: 0,
+                mouse_btn: MouseButton::Right,
+                clicks: 2,
+                x: 10,
+                y: -3,
+            },
+            Event::MouseWheel {
+                timestamp: 8,
+                window_id: 1,
+                which: 0,
+                x: 0,
+                y: -1,
+                direction: MouseWheelDirection::Flipped,
+                precise_x: 0.5,
+                precise_y: -1.25,
+                mouse_x: 100,
+                mouse_y: 200,
+            },
+            Event::ControllerButtonDown {
+                timestamp: 9,
+                which: 0,
+                button: Button::DPadUp,
+            },
+            Event::ControllerAxisMotion {
+                timestamp: 10,
+                which: 0,
+                axis: Axis::TriggerLeft,
+                value: -32768,
+            },
+            Event::FingerMotion {
+                timestamp: 11,
+                touch_id: 1,
+                finger_id: 2,
+                x: 0.25,
+                y: 0.75,
+                dx: 0.0,
+                dy: -0.125,
+                pressure: 1.0,
+            },
+            Event::DropFile {
+                timestamp: 12,
+                window_id: 1,
+                filename: String::from("/tmp/some file\n.txt"),
+            },
+        ];
+        for event in events {
+            let line = encode(&event).unwrap();
+            assert!(!line.contains('\n'), "{}", line);
+            assert_eq!(decode(&line), Ok(event));
+        }
+    }
+
+    #[test]
+    fn frames() {
+        let path = std::env::temp_dir().join(format!("replay-test-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let quit = Event::Quit { timestamp: 3 };
+        {
+            let mut recorder = Recorder::new(path).unwrap();
+            recorder.record_frame(0.016, &[]);
+            recorder.record_frame(1.0 / 3.0, &[quit.clone(), quit.clone()]);
+        }
+        let mut replay = Replay::new(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(replay.next_frame(), (Some(0.016), vec![]));
+        assert!(!replay.is_finished());
+        assert_eq!(
+            replay.next_frame(),
+            (Some(1.0 / 3.0), vec![quit.clone(), quit])
+        );
+        assert!(replay.is_finished());
+        assert_eq!(replay.next_frame(), (None, vec![]));
+    }
+
+    #[test]
+    fn unrecorded() {
+        assert_eq!(
+            encode(&Event::User {
+                timestamp: 0,
+                window_id: 0,
+                type_: 0,
+                code: 0,
+                data1: std::ptr::null_mut(),
+                data2: std::ptr::null_mut(),
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        for s in [
+            "",
+            "plain",
+            "two words",
+            "\\s",
+            "a\tb\r\nc",
+            "\\",
+            "ünïcödé",
+        ] {
+            let escaped = escape(s);
+            assert!(!escaped.contains([' ', '\n', '\r', '\t']), "{}", escaped);
+            assert_eq!(unescape(&escaped).as_deref(), Ok(s));
+        }
+        assert_eq!(escape("a b\\"), "a\\sb\\\\");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(unescape("trailing\\").is_err());
+        assert!(unescape("\\x").is_err());
+        assert!(decode("1 Nonsense 2").is_err());
+        assert!(decode("1 KeyDown 1 - -").is_err());
+        assert!(decode("x Quit").is_err());
+    }
+}
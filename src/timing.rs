@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+/// Timing of the current frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    /// Seconds since the last frame, clamped after stalls.
+    pub dt: f64,
+    /// Seconds simulated so far, which advances in fixed steps.
+    pub elapsed: f64,
+    /// How far along the next fixed step we are, from 0 to 1, for interpolating.
+    pub alpha: f64,
+    pub frame: u64,
+}
+
+/// Timing of the current frame, for Lua and anything else without access to the host.
+pub static TIMING: std::sync::Mutex<Timing> = std::sync::Mutex::new(Timing {
+    dt: 0.0,
+    elapsed: 0.0,
+    alpha: 0.0,
+    frame: 0,
+});
+
+/// Measures frame times and splits them into fixed simulation steps, so that the game runs at
+/// the same speed whatever the refresh rate.
+pub struct FrameTimer {
+    /// Length of a simulation step in seconds.
+    pub fixed_step: f64,
+    /// Longest frame time to simulate, so that the game doesn't try to catch up after a stall.
+    pub max_frame_time: f64,
+    /// Frames per second to limit to, if any.
+    pub fps_cap: Option<f64>,
+    last: Instant,
+    accumulator: f64,
+    timing: Timing,
+}
+
+impl Default for FrameTimer {
+    fn default() -> FrameTimer {
+        FrameTimer::new()
+    }
+}
+
+impl FrameTimer {
+    pub fn new() -> FrameTimer {
+        FrameTimer {
+            fixed_step: 1.0 / 60.0,
+            max_frame_time: 0.25,
+            fps_cap: None,
+            last: Instant::now(),
+            accumulator: 0.0,
+            timing: *TIMING.lock().unwrap(),
+        }
+    }
+
+    /// Starts a new frame, returning when it started. When replaying, `recorded` is the length
+    /// the frame had when it was recorded, which is used instead of the measured one so that the
+    /// simulation takes the same steps.
+    pub fn begin_frame(&mut self, recorded: Option<f64>) -> Instant {
+        let now = Instant::now();
+        let dt = recorded
+            .unwrap_or_else(|| now.duration_since(self.last).as_secs_f64())
+            .min(self.max_frame_time);
+        self.last = now;
+        self.accumulator += dt;
+        self.timing.dt = dt;
+        self.timing.frame += 1;
+        now
+    }
+
+    /// Takes a fixed step out of the time to simulate, returning false once there isn't enough
+    /// left for one.
+    pub fn step(&mut self) -> bool {
+        if self.accumulator < self.fixed_step {
+            self.timing.alpha = self.accumulator / self.fixed_step;
+            *TIMING.lock().unwrap() = self.timing;
+            return false;
+        }
+        self.accumulator -= self.fixed_step;
+        self.timing.elapsed += self.fixed_step;
+        true
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    /// Sleeps off what is left of the frame when there is an FPS cap.
+    pub fn limit(&self) {
+        if let Some(fps) = self.fps_cap {
+            let frame = Duration::from_secs_f64(1.0 / fps);
+            if let Some(left) = frame.checked_sub(self.last.elapsed()) {
+                std::thread::sleep(left);
+            }
+        }
+    }
+}
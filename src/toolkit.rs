@@ -1,10 +1,12 @@
-use crate::toolkit_lua::ToolkitWindowLua;
+use crate::focus::{FindStops, Focus, Stop, Stops};
+use crate::iced_sdl::EventInfo;
 use iced::border::Border;
 use iced::theme::palette::{Background, Danger, Extended, Pair, Primary, Secondary, Success};
 use iced::theme::Palette;
 use iced::widget::container::Style;
-use iced_core::{Color, Element, Theme};
-use iced_lua::Message as MessageLua;
+use iced_core::event::Status;
+use iced_core::widget::operation::Operation;
+use iced_core::{Color, Element, Rectangle, Theme};
 use iced_runtime::Task;
 use iced_wgpu::{wgpu, Renderer};
 
@@ -54,6 +56,21 @@ pub fn window(theme: &Theme) -> Style {
 pub trait Window {
     fn update(&mut self, message: Message) -> Message;
     fn view(&self) -> Element<'_, Message, Theme, Renderer>;
+    /// Message to send when the user backs out of the window, e.g., with a controller.
+    fn cancel(&self) -> Message {
+        Message::CloseWindow
+    }
+}
+
+/// Directions and actions used to drive the toolkit without a mouse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Navigation {
+    Up,
+    Down,
+    Left,
+    Right,
+    Activate,
+    Cancel,
 }
 
 #[derive(Debug, Clone)]
@@ -87,6 +104,10 @@ impl Window for DlgOK {
         }
     }
 
+    fn cancel(&self) -> Message {
+        Message::Dialogue(MessageDialogue::Accept)
+    }
+
     fn view(&self) -> Element<Message, Theme, Renderer> {
         use iced::{color, Center, Fill};
         use iced_widget::{button, column, container, text};
@@ -151,6 +172,10 @@ impl Window for DlgInput {
             Message::None
         }
     }
+    fn cancel(&self) -> Message {
+        Message::Dialogue(MessageDialogue::Cancel)
+    }
+
     fn view(&self) -> Element<Message, Theme, Renderer> {
         use iced::{color, Center, Fill};
         use iced_widget::{button, column, container, row, text, text_input};
@@ -181,63 +206,75 @@ impl Window for DlgInput {
     }
 }
 
-pub enum ToolkitWindow {
-    Lua(ToolkitWindowLua),
-    MenuMain(crate::menu_main::MenuMain),
-    DlgOK(DlgOK),
-    DlgInput(DlgInput),
+/// Something dragged from outside the game and dropped on a window.
+#[derive(Debug, Clone)]
+pub enum Dropped {
+    File(std::path::PathBuf),
+    Text(String),
 }
 
+/// Makes a window to open.
+pub type OpenWindow = std::sync::Arc<dyn Fn() -> Box<dyn Window> + Send + Sync>;
+
 #[derive(Clone)]
 pub enum Message {
     None,
     CloseWindow,
     CloseWindows(u32),
-    OpenMenuMain,
-    OpenLua(ToolkitWindowLua),
+    Open(OpenWindow),
     OpenDialogueOK(String, &'static (dyn Fn() -> Message + Send + Sync)),
     OpenDialogueInput(
         String,
         &'static (dyn Fn(bool, String) -> Message + Send + Sync),
     ),
-    Lua(MessageLua),
-    MenuMain(crate::menu_main::Message),
+    Preedit(Option<(String, Rectangle)>),
+    Dropped(Dropped),
+    /// Message of a window the toolkit doesn't know about, see [`Message::custom`].
+    Custom(std::sync::Arc<dyn std::any::Any + Send + Sync>),
     Dialogue(MessageDialogue),
 }
-impl std::fmt::Debug for Message {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        match self {
-            Message::OpenDialogueOK(s, _) => write!(f, "OpenDialogueOK( {}, Fn )", s),
-            o => o.fmt(f),
-        }
+
+impl Message {
+    /// Opens the window `open` makes.
+    pub fn open<W: Window + 'static>(open: impl Fn() -> W + Send + Sync + 'static) -> Message {
+        Message::Open(std::sync::Arc::new(move || Box::new(open())))
     }
-}
 
-impl Window for ToolkitWindow {
-    fn update(&mut self, message: Message) -> Message {
+    /// Wraps a message of the game's own windows.
+    pub fn custom(message: impl std::any::Any + Send + Sync) -> Message {
+        Message::Custom(std::sync::Arc::new(message))
+    }
+
+    /// The message given to [`Message::custom`], if it is a `T`.
+    pub fn downcast<T: std::any::Any>(&self) -> Option<&T> {
         match self {
-            ToolkitWindow::Lua(state) => state.update(message),
-            ToolkitWindow::MenuMain(state) => state.update(message),
-            ToolkitWindow::DlgOK(state) => state.update(message),
-            ToolkitWindow::DlgInput(state) => state.update(message),
-            //_ => Task::none(),
+            Message::Custom(message) => message.downcast_ref(),
+            _ => None,
         }
     }
+}
 
-    fn view(&self) -> iced_core::Element<Message, Theme, Renderer> {
+impl std::fmt::Debug for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
-            ToolkitWindow::Lua(state) => state.view(),
-            ToolkitWindow::MenuMain(state) => state.view(),
-            ToolkitWindow::DlgOK(state) => state.view(),
-            ToolkitWindow::DlgInput(state) => state.view(),
-            //_ => iced_widget::text("").into(),
+            Message::None => write!(f, "None"),
+            Message::CloseWindow => write!(f, "CloseWindow"),
+            Message::CloseWindows(n) => write!(f, "CloseWindows({})", n),
+            Message::Open(_) => write!(f, "Open(Fn)"),
+            Message::OpenDialogueOK(s, _) => write!(f, "OpenDialogueOK({:?}, Fn)", s),
+            Message::OpenDialogueInput(s, _) => write!(f, "OpenDialogueInput({:?}, Fn)", s),
+            Message::Preedit(p) => write!(f, "Preedit({:?})", p),
+            Message::Dropped(d) => write!(f, "Dropped({:?})", d),
+            Message::Custom(_) => write!(f, "Custom(..)"),
+            Message::Dialogue(m) => write!(f, "Dialogue({:?})", m),
         }
     }
 }
 
 pub struct ToolkitProgram {
     pub open: bool,
-    pub windows: Vec<ToolkitWindow>,
+    pub windows: Vec<Box<dyn Window>>,
+    preedit: Option<(String, Rectangle)>,
 }
 
 impl ToolkitProgram {
@@ -245,16 +282,27 @@ impl ToolkitProgram {
         ToolkitProgram {
             open: false,
             windows: Vec::new(),
+            preedit: None,
         }
     }
 
     pub fn window_update(&mut self, message: Message) -> Task<Message> {
+        if let Message::Preedit(preedit) = message {
+            self.preedit = preedit;
+            return Task::none();
+        }
         let t = window_message(&mut self.windows, message, true);
         self.open = !self.windows.is_empty();
         t
     }
 }
 
+impl Default for ToolkitProgram {
+    fn default() -> ToolkitProgram {
+        ToolkitProgram::new()
+    }
+}
+
 #[allow(dead_code)]
 pub fn dialogue_noop_ok() -> Message {
     Message::CloseWindow
@@ -265,7 +313,7 @@ pub fn dialogue_noop_input(_b: bool, _s: String) -> Message {
 }
 
 fn window_message(
-    windows: &mut Vec<ToolkitWindow>,
+    windows: &mut Vec<Box<dyn Window>>,
     message: Message,
     recurse: bool,
 ) -> Task<Message> {
@@ -282,22 +330,18 @@ fn window_message(
             }
             Task::none()
         }
-        Message::OpenMenuMain => {
-            windows.push(ToolkitWindow::MenuMain(crate::menu_main::MenuMain::new()));
-            Task::none()
-        }
-        Message::OpenLua(tk) => {
-            windows.push(ToolkitWindow::Lua(tk));
+        Message::Open(open) => {
+            windows.push(open());
             Task::none()
         }
         Message::OpenDialogueOK(msg, accept) => {
-            windows.push(ToolkitWindow::DlgOK(DlgOK::new(msg, accept)));
+            windows.push(Box::new(DlgOK::new(msg, accept)));
             Task::none()
         }
         Message::OpenDialogueInput(msg, accept) => {
             let w = DlgInput::new(msg, accept);
             let t = w.focus();
-            windows.push(ToolkitWindow::DlgInput(w));
+            windows.push(Box::new(w));
             t
         }
         _ => {
@@ -326,14 +370,60 @@ impl iced_runtime::Program for ToolkitProgram {
     }
 
     fn view(&self) -> Element<'_, Message, Theme, Renderer> {
-        let ele: Vec<Element<'_, Message, Theme, Renderer>> =
-            self.windows.iter().map(|w| w.view()).collect();
+        use iced::Fill;
+        use iced_widget::{container, text};
+        let mut ele: Vec<Element<'_, Message, Theme, Renderer>> = self
+            .windows
+            .iter()
+            .enumerate()
+            .map(|(i, w)| container(Stops::new(w.view())).id(window_id(i)).into())
+            .collect();
+        if let Some((preedit, bounds)) = &self.preedit {
+            ele.push(
+                container(container(text(preedit.as_str())).style(window).padding(5))
+                    .padding(iced::Padding {
+                        top: bounds.y + bounds.height,
+                        left: bounds.x,
+                        ..iced::Padding::ZERO
+                    })
+                    .width(Fill)
+                    .height(Fill)
+                    .into(),
+            );
+        }
         iced_widget::Stack::with_children(ele).into()
     }
 }
 
+/// Identifies the container wrapping each window, so operations can tell them apart.
+fn window_id(index: usize) -> iced_widget::container::Id {
+    iced_widget::container::Id::new(format!("toolkit-window-{}", index))
+}
+
+/// Whether iced might move the focus on an event. Anything else, such as the cursor moving,
+/// leaves it where it was.
+fn can_focus(event: &iced_core::Event) -> bool {
+    use iced_core::{keyboard, mouse, touch, Event};
+    matches!(
+        event,
+        Event::Mouse(mouse::Event::ButtonPressed(_))
+            | Event::Touch(touch::Event::FingerPressed { .. })
+            | Event::Keyboard(keyboard::Event::KeyPressed { .. })
+    )
+}
+
 pub static MESSAGE_QUEUE: std::sync::Mutex<Vec<Message>> = std::sync::Mutex::new(Vec::new());
 
+/// An event queued since the last update, along with what iced can't tell about it.
+enum Queued {
+    /// Given to iced, and reported back with how iced handled it.
+    Sent(iced_core::Event, EventInfo),
+    /// Reported back as ignored without iced seeing it, as no window was open.
+    Ignored(iced_core::Event, EventInfo),
+    /// One of our own, given to iced but not reported back.
+    Synthetic,
+}
+
 pub struct Toolkit<'a> {
     theme: Theme,
     device: &'a wgpu::Device,
@@ -342,7 +432,14 @@ pub struct Toolkit<'a> {
     viewport: iced_wgpu::graphics::Viewport,
     debug: iced_runtime::Debug,
     cursor_position: iced_core::mouse::Cursor,
-    state: iced_runtime::program::State<ToolkitProgram>,
+    state: crate::program::State<ToolkitProgram>,
+    text_input: Option<Rectangle>,
+    preedit: Option<(String, Rectangle)>,
+    nav_index: Option<usize>,
+    nav_windows: usize,
+    // Whether something happened that can move the focus since we last looked for a text input
+    focus_changed: bool,
+    events: Vec<Queued>,
 }
 
 impl<'a> Toolkit<'a> {
@@ -365,15 +462,16 @@ impl<'a> Toolkit<'a> {
             scale_factor,
         );
         let mut debug = iced_runtime::Debug::new();
-        let mut state = iced_runtime::program::State::new(
+        let mut state = crate::program::State::new(
             ToolkitProgram::new(),
             viewport.logical_size(),
             &mut renderer,
             &mut debug,
         );
-        state.queue_event(iced::Event::Window(iced::window::Event::RedrawRequested(
+        let redraw = iced::Event::Window(iced::window::Event::RedrawRequested(
             std::time::Instant::now(),
-        )));
+        ));
+        state.queue_event(redraw);
         Toolkit {
             theme: iced::theme::Theme::custom(String::from("Naev"), PALETTE),
             device,
@@ -383,40 +481,235 @@ impl<'a> Toolkit<'a> {
             debug,
             cursor_position: iced_core::mouse::Cursor::Unavailable,
             state,
+            text_input: None,
+            preedit: None,
+            nav_index: None,
+            nav_windows: 0,
+            focus_changed: true,
+            events: vec![Queued::Synthetic],
         }
     }
 
+    /// Changes the physical size of the area the toolkit draws to. The layout follows on the
+    /// next update, as iced lays out again whenever the bounds change.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.viewport = iced_wgpu::graphics::Viewport::with_physical_size(
+            iced::Size::new(width, height),
+            self.viewport.scale_factor(),
+        );
+        self.focus_changed = true;
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.viewport = iced_wgpu::graphics::Viewport::with_physical_size(
+            self.viewport.physical_size(),
+            scale_factor,
+        );
+        self.focus_changed = true;
+    }
+
+    /// Sets the cursor position in physical pixels.
     pub fn update_cursor_position(&mut self, x: f32, y: f32) {
         let s = 1.0 / self.viewport.scale_factor() as f32;
         self.cursor_position =
             iced_core::mouse::Cursor::Available(iced_core::Point::new(x * s, y * s));
     }
 
-    pub fn queue_event(&mut self, event: iced_core::Event) {
-        if !self.state.program().open {
-            return;
+    /// Queues an event for the next update. Events are only given to iced while a window is
+    /// open, otherwise they are reported back as ignored. `info` is passed along to the game,
+    /// as iced has no place for it.
+    pub fn queue_event(&mut self, event: iced_core::Event, info: EventInfo) {
+        if self.state.program().open {
+            self.focus_changed |= can_focus(&event);
+            self.state.queue_event(event.clone());
+            self.events.push(Queued::Sent(event, info));
+        } else {
+            self.events.push(Queued::Ignored(event, info));
         }
-        self.state.queue_event(event)
+    }
+
+    /// Queues an event of our own, which iced handles like any other but the game never sees.
+    fn queue_synthetic(&mut self, event: iced_core::Event) {
+        self.state.queue_event(event);
+        self.events.push(Queued::Synthetic);
+    }
+
+    /// Tells the widgets a new frame is coming, so they can animate.
+    pub fn redraw(&mut self, now: std::time::Instant) {
+        self.queue_synthetic(iced::Event::Window(iced::window::Event::RedrawRequested(
+            now,
+        )));
     }
 
     pub fn queue_message(&mut self, message: Message) {
-        self.state.queue_message(message)
+        self.state.queue_message(message);
+        self.focus_changed = true;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state.program().open
     }
 
+    /// How the cursor should look, or `None` when the toolkit isn't showing anything.
+    pub fn mouse_interaction(&self) -> Option<iced_core::mouse::Interaction> {
+        match self.state.program().open {
+            true => Some(self.state.mouse_interaction()),
+            false => None,
+        }
+    }
+
+    /// Shows the text being composed by the input method over the focused text input.
+    pub fn set_preedit(&mut self, preedit: Option<&str>) {
+        let preedit = preedit
+            .zip(self.text_input)
+            .map(|(s, bounds)| (String::from(s), bounds));
+        if preedit != self.preedit {
+            self.preedit = preedit.clone();
+            self.queue_message(Message::Preedit(preedit));
+        }
+    }
+
+    /// Area of the focused text input in physical pixels, if there is one.
+    pub fn text_input_rect(&self) -> Option<Rectangle> {
+        self.text_input
+            .map(|bounds| bounds * self.viewport.scale_factor() as f32)
+    }
+
+    /// Moves focus around the buttons and focusable widgets of the top window, or acts on the
+    /// one with focus. Focusable widgets are focused, while buttons get the cursor moved over
+    /// them, so activating them is a plain click. These events don't come from the player, so
+    /// they go straight to iced instead of being reported back.
+    pub fn navigate(&mut self, navigation: Navigation) {
+        let nw = self.state.program().windows.len();
+        if nw == 0 {
+            return;
+        }
+        if nw != self.nav_windows {
+            self.nav_windows = nw;
+            self.nav_index = None;
+        }
+
+        match navigation {
+            Navigation::Cancel => {
+                if let Some(w) = self.state.program().windows.last() {
+                    match w.cancel() {
+                        Message::None => (),
+                        m => self.queue_message(m),
+                    }
+                }
+            }
+            Navigation::Activate => {
+                let Some(index) = self.nav_index else {
+                    return;
+                };
+                // Focusable widgets already have focus, so there's only buttons to press
+                let stop = self.stops().get(index).copied();
+                if let Some(Stop {
+                    focusable: None, ..
+                }) = stop
+                {
+                    use iced_core::mouse;
+                    for e in [
+                        mouse::Event::ButtonPressed(mouse::Button::Left),
+                        mouse::Event::ButtonReleased(mouse::Button::Left),
+                    ] {
+                        self.queue_synthetic(iced_core::Event::Mouse(e));
+                    }
+                    self.focus_changed = true;
+                }
+            }
+            Navigation::Up | Navigation::Down | Navigation::Left | Navigation::Right => {
+                let stops = self.stops();
+                let count = stops.len();
+                if count == 0 {
+                    return;
+                }
+                let forward = matches!(navigation, Navigation::Down | Navigation::Right);
+                let index = match (self.nav_index, forward) {
+                    (None, true) => 0,
+                    (None, false) => count - 1,
+                    (Some(i), true) => (i + 1) % count,
+                    (Some(i), false) => (i + count - 1) % count,
+                };
+                self.nav_index = Some(index);
+                let stop = stops[index];
+                self.focus(stop.focusable);
+                if stop.focusable.is_none() {
+                    let position = stop.bounds.center();
+                    self.cursor_position = iced_core::mouse::Cursor::Available(position);
+                    self.queue_synthetic(iced_core::Event::Mouse(
+                        iced_core::mouse::Event::CursorMoved { position },
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Focuses the focusable widget at `target` in the top window, unfocusing the others.
+    fn focus(&mut self, target: Option<usize>) {
+        let operation = Focus {
+            top: window_id(self.state.program().windows.len() - 1).into(),
+            in_top: false,
+            target,
+            count: 0,
+        };
+        self.state.operate(
+            &mut self.renderer,
+            std::iter::once(Box::new(operation) as Box<dyn Operation>),
+            self.viewport.logical_size(),
+            &mut self.debug,
+        );
+        self.focus_changed = true;
+    }
+
+    /// Buttons and focusable widgets of the top window, in order.
+    fn stops(&mut self) -> Vec<Stop> {
+        let nw = self.state.program().windows.len();
+        if nw == 0 {
+            return Vec::new();
+        }
+        let stops = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let operation = FindStops {
+            top: window_id(nw - 1).into(),
+            in_top: false,
+            stops: stops.clone(),
+        };
+        self.state.operate(
+            &mut self.renderer,
+            std::iter::once(Box::new(operation) as Box<dyn Operation>),
+            self.viewport.logical_size(),
+            &mut self.debug,
+        );
+        let stops = std::mem::take(&mut *stops.lock().unwrap());
+        stops
+    }
+
+    fn focused_text_input(&mut self) -> Option<Rectangle> {
+        self.stops()
+            .into_iter()
+            .find(|stop| stop.text && stop.focused)
+            .map(|stop| stop.bounds)
+    }
+
+    /// Number of windows open, from the bottom one up.
+    pub fn window_count(&self) -> usize {
+        self.state.program().windows.len()
+    }
+
+    /// Updates the toolkit, returning the events queued since the last update along with
+    /// whether the toolkit captured them and what iced can't tell about them.
     pub fn update(
         &mut self,
         clipboard: &mut impl iced_core::Clipboard,
-        lua_th: &mut Option<mlua::Thread>,
-    ) {
-        let mut mq = MESSAGE_QUEUE.lock().unwrap();
+    ) -> Vec<(iced_core::Event, Status, EventInfo)> {
+        // Windows can queue more messages as they update, so the queue can't stay locked
+        let mut mq = std::mem::take(&mut *MESSAGE_QUEUE.lock().unwrap());
         while let Some(m) = mq.pop() {
             self.queue_message(m);
         }
 
-        let nw = self.state.program().windows.len();
-
         // We update iced
-        let _ = self.state.update(
+        let (statuses, _) = self.state.update(
             self.viewport.logical_size(),
             self.cursor_position,
             &mut self.renderer,
@@ -425,18 +718,26 @@ impl<'a> Toolkit<'a> {
             clipboard,
             &mut self.debug,
         );
+        if std::mem::take(&mut self.focus_changed) {
+            self.text_input = self.focused_text_input();
+        }
 
-        // Run Lua if window was closed. TODO check if window was closed and another was opened
-        if let Some(th) = lua_th {
-            if self.state.program().windows.len() < nw {
-                dbg!(nw, self.state.program().windows.len());
-                dbg!("resume");
-                th.resume::<()>(()).unwrap();
-                if th.status() != mlua::ThreadStatus::Resumable {
-                    *lua_th = None;
+        // iced tells how it handled each event it was given, in the order they were queued
+        let mut statuses = statuses.into_iter();
+        std::mem::take(&mut self.events)
+            .into_iter()
+            .filter_map(|queued| match queued {
+                Queued::Sent(event, info) => {
+                    let status = statuses.next().unwrap_or(Status::Ignored);
+                    Some((event, status, info))
                 }
-            }
-        };
+                Queued::Ignored(event, info) => Some((event, Status::Ignored, info)),
+                Queued::Synthetic => {
+                    statuses.next();
+                    None
+                }
+            })
+            .collect()
     }
 
     pub fn draw(
@@ -444,9 +745,10 @@ impl<'a> Toolkit<'a> {
         engine: &mut iced_wgpu::Engine,
         view: &wgpu::TextureView,
         encoder: &mut wgpu::CommandEncoder,
-        frame: &wgpu::SurfaceTexture,
+        format: wgpu::TextureFormat,
+        background: Option<Color>,
     ) {
-        if !self.state.program().open {
+        if !self.state.program().open && background.is_none() {
             return;
         }
 
@@ -455,8 +757,8 @@ impl<'a> Toolkit<'a> {
             self.device,
             self.queue,
             encoder,
-            None,
-            frame.texture.format(),
+            background,
+            format,
             view,
             &self.viewport,
             &self.debug.overlay(),
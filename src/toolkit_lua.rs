@@ -1,7 +1,8 @@
-use crate::toolkit;
-use crate::toolkit::Message;
 use iced_core::Theme;
+use iced_lua::Message as MessageLua;
 use iced_wgpu::Renderer;
+use integration::toolkit;
+use integration::toolkit::Message;
 
 pub fn open_iced(lua: &mlua::Lua) -> mlua::Result<()> {
     let globals = lua.globals();
@@ -10,10 +11,11 @@ pub fn open_iced(lua: &mlua::Lua) -> mlua::Result<()> {
     iced.set(
         "_run",
         lua.create_function(|_lua, (update, view): (mlua::Function, mlua::Function)| {
+            let window = ToolkitWindowLua::new(update, view)?;
             toolkit::MESSAGE_QUEUE
                 .lock()
                 .unwrap()
-                .push(Message::OpenLua(ToolkitWindowLua::new(update, view)?));
+                .push(Message::open(move || window.clone()));
             Ok(())
         })?,
     )?;
@@ -43,16 +45,30 @@ impl ToolkitWindowLua {
 
 impl toolkit::Window for ToolkitWindowLua {
     fn update(&mut self, message: Message) -> Message {
-        match message {
-            Message::Lua(m) => {
-                let finish = self.update.call::<bool>(m.0).unwrap_or_else(|err| {
-                    panic!("{}", err);
-                });
-                if finish {
-                    return Message::CloseWindow;
-                }
+        let finish = match message {
+            Message::Dropped(d) => {
+                let msg: std::collections::HashMap<&str, String> = match d {
+                    toolkit::Dropped::File(path) => [
+                        ("event", String::from("file_dropped")),
+                        ("path", path.to_string_lossy().into_owned()),
+                    ]
+                    .into(),
+                    toolkit::Dropped::Text(text) => {
+                        [("event", String::from("text_dropped")), ("text", text)].into()
+                    }
+                };
+                self.update.call::<bool>(msg)
             }
-            _ => unreachable!(),
+            m => match m.downcast::<MessageLua>() {
+                Some(m) => self.update.call::<bool>(m.0.clone()),
+                None => unreachable!(),
+            },
+        }
+        .unwrap_or_else(|err| {
+            panic!("{}", err);
+        });
+        if finish {
+            return Message::CloseWindow;
         }
         Message::None
     }
@@ -64,6 +80,6 @@ impl toolkit::Window for ToolkitWindowLua {
         .unwrap_or_else(|err| {
             panic!("{}", err);
         })
-        .map(|m| Message::Lua(m))
+        .map(Message::custom)
     }
 }
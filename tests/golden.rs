@@ -0,0 +1,10 @@
+//! Compares the built-in windows against the golden images, see `src/golden.rs`.
+
+#[test]
+fn golden() {
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_integration"))
+        .arg("golden")
+        .status()
+        .expect("failed to run the golden image comparison");
+    assert!(status.success(), "golden images differ, see above");
+}